@@ -14,7 +14,7 @@ fn is_nixos() -> bool {
         }
     }
 
-    return false;
+    false
 }
 
 fn main() {
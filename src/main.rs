@@ -5,33 +5,57 @@ extern crate static_assertions;
 
 mod shared;
 
-use crate::shared::VERBOSE;
-use crate::shared::args::{RssArgs, RssSubcommand};
-use crate::shared::cat::cat;
-use crate::shared::config::{edit_config, get_config, get_config_path, reset_config};
+use crate::shared::args::{ConfigAction, OutputFormat, RssArgs, RssSubcommand, TemplateAction};
+use crate::shared::cat::{cat, filter_from_args};
+use crate::shared::completions::{completions, handle_completion_request, man};
+use crate::shared::config::editors::quick_editor;
+use crate::shared::config::{
+    config_get, config_schema, config_set, config_unset, edit_config, get_config, get_config_path,
+    list_editors, reset_config, show_config_origins,
+};
 use crate::shared::ctrl_c_handler::init_ctrl_c_handler;
-use crate::shared::edit::edit;
+use crate::shared::diff::diff;
+use crate::shared::edit::{EditMode, NewProjectSource, edit};
+use crate::shared::export::export;
 use crate::shared::extract::extract;
+use crate::shared::git::{git_setup, merge_driver, textconv};
+use crate::shared::history::{history, revert, show};
+use crate::shared::import::import;
+use crate::shared::info::info;
 use crate::shared::install::install;
-use crate::shared::pack::pack;
+use crate::shared::modify::{mv, put, rm};
+use crate::shared::pack::{PackSource, pack};
 use crate::shared::recompile::recompile;
+use crate::shared::restore::restore;
+use crate::shared::shell::shell_command;
 use crate::shared::stats::stats;
 use crate::shared::strip::strip;
+use crate::shared::sync::sync;
+use crate::shared::template::{template_add, template_ls, template_rm};
 use crate::shared::tree::tree;
+use crate::shared::util::binaries::BinaryTarget;
+use crate::shared::util::print_json;
+use crate::shared::util::zip::CatOptions;
 use crate::shared::wrapped_run::wrapped_run;
+use crate::shared::{CONFIG_OVERRIDE, JSON_OUTPUT, VERBOSE};
 use clap::Parser;
 use color_print::cprintln;
 use colored_json::ToColoredJson;
-use std::path::PathBuf;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use std::process::exit;
 
 fn main() {
     #[cfg(all(not(windows), not(unix)))]
     compile_error!("Only Windows and Unix-derivatives are supported");
 
+    handle_completion_request();
+
     init_ctrl_c_handler();
 
     if let Err(e) = wrapped_main() {
         cprintln!("\n<red, bold>{e}</>");
+        exit(1);
     }
 }
 
@@ -47,72 +71,329 @@ fn wrapped_main() -> Result<(), String> {
 
     let args = RssArgs::parse();
     VERBOSE.set(args.verbose()).unwrap();
+    JSON_OUTPUT
+        .set(*args.format() == OutputFormat::Json)
+        .unwrap();
+    if let Some(config) = args.config() {
+        CONFIG_OVERRIDE.set(PathBuf::from(config)).unwrap();
+    }
 
     match args.subcommand() {
         RssSubcommand::Readme => {
             println!("{}", include_str!("../README.md"));
         }
-        RssSubcommand::Run { file, args } => {
-            wrapped_run(file, args)?;
+        RssSubcommand::Run {
+            file,
+            bin,
+            example,
+            args,
+        } => {
+            let target = match (bin, example) {
+                (Some(bin), _) => Some(BinaryTarget::bin(bin)),
+                (_, Some(example)) => Some(BinaryTarget::example(example)),
+                (None, None) => None,
+            };
+            wrapped_run(file, target.as_ref(), args)?;
         }
-        RssSubcommand::Edit { file } | RssSubcommand::New { file } => {
-            let new = matches!(args.subcommand(), RssSubcommand::New { .. });
+        RssSubcommand::Edit {
+            file,
+            editor,
+            quick,
+            quick_file,
+        } => {
+            let mut config = get_config()?;
+            let mode = if *quick {
+                EditMode::Quick(quick_file.as_deref().unwrap_or("src/main.rs"))
+            } else {
+                EditMode::Editor
+            };
+            if let EditMode::Quick(quick_file) = mode {
+                config.override_edit_command(quick_editor(quick_file)?);
+            } else {
+                config.select_editor(editor.clone());
+            }
+            config.project_edit_command()?;
+            edit(
+                &config,
+                PathBuf::from(file),
+                false,
+                NewProjectSource::Default,
+                mode,
+            )?;
+        }
+        RssSubcommand::New {
+            file,
+            template,
+            from,
+            editor,
+        } => {
+            let source = match (template, from) {
+                (Some(template), _) => NewProjectSource::Template(template),
+                (_, Some(from)) => NewProjectSource::Script(Path::new(from)),
+                (None, None) => NewProjectSource::Default,
+            };
 
+            let mut config = get_config()?;
+            config.select_editor(editor.clone());
+            config.project_edit_command()?;
+            edit(&config, PathBuf::from(file), true, source, EditMode::Editor)?;
+        }
+        RssSubcommand::Shell { file } => {
+            let mut config = get_config()?;
+            let name = Path::new(file)
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            // Holds the shell's startup files until it exits
+            let (command, _startup_dir) = shell_command(&name)?;
+            config.override_edit_command(command);
+            edit(
+                &config,
+                PathBuf::from(file),
+                false,
+                NewProjectSource::Default,
+                EditMode::Shell,
+            )?;
+        }
+        RssSubcommand::Import {
+            script,
+            output,
+            force,
+        } => {
             let config = get_config()?;
-            edit(&config, PathBuf::from(file), new)?;
+            import(&config, script, output.as_deref(), *force)?;
         }
         RssSubcommand::Install { file } => {
             let config = get_config()?;
             install(&config, PathBuf::from(file))?;
         }
-        RssSubcommand::Strip { file } => {
+        RssSubcommand::Strip { file, history } => {
             let config = get_config()?;
-            strip(&config, PathBuf::from(file))?;
+            strip(&config, PathBuf::from(file), *history)?;
         }
         RssSubcommand::Recompile { file } => {
             let config = get_config()?;
             recompile(&config, PathBuf::from(file))?;
         }
-        RssSubcommand::Config { reset, r#where } => {
-            if !reset && !r#where {
+        RssSubcommand::Config {
+            action: Some(action),
+            ..
+        } => match action {
+            ConfigAction::Get { key } => {
+                let config = get_config()?;
+                config_get(&config, key)?;
+            }
+            ConfigAction::Set { key, value } => {
+                let config = get_config()?;
+                config_set(&config, key, value)?;
+            }
+            ConfigAction::Unset { key } => {
+                let config = get_config()?;
+                config_unset(&config, key)?;
+            }
+            ConfigAction::Schema => config_schema()?,
+            ConfigAction::Editors => {
+                let config = get_config()?;
+                list_editors(&config)?;
+            }
+        },
+        RssSubcommand::Config {
+            reset,
+            r#where,
+            show_origin,
+            action: None,
+        } => {
+            if *show_origin {
+                let config = get_config()?;
+                show_config_origins(&config)?;
+            } else if !reset && !r#where {
                 // Normal config
                 let config = get_config()?;
                 edit_config(&config)?;
             } else if *r#where && !reset {
                 // Where
-                println!(
-                    "Config at '{}'",
-                    get_config_path()?.as_os_str().to_string_lossy()
-                );
+                let path = get_config_path()?;
+                if *JSON_OUTPUT.get().unwrap() {
+                    print_json(&json!({ "path": path }))?;
+                } else {
+                    println!("Config at '{}'", path.as_os_str().to_string_lossy());
+                }
             } else {
                 // Reset
                 let (p, json) = reset_config()?;
-                println!("Reset config to:\n{}", json.to_colored_json_auto().unwrap());
-                println!("Reset config at '{p}'");
+                if *JSON_OUTPUT.get().unwrap() {
+                    let config: serde_json::Value = serde_json::from_str(&json)
+                        .map_err(|e| format!("E224 Failed to parse config: {e}"))?;
+                    print_json(&json!({ "path": p, "config": config }))?;
+                } else {
+                    println!("Reset config to:\n{}", json.to_colored_json_auto().unwrap());
+                    println!("Reset config at '{p}'");
+                }
+            }
+        }
+        RssSubcommand::Extract {
+            file,
+            output,
+            force,
+            only,
+            with_binary,
+            link,
+        } => {
+            let config = get_config()?;
+            extract(
+                &config,
+                file,
+                output.as_deref(),
+                *force,
+                only,
+                *with_binary,
+                *link,
+            )?;
+        }
+        RssSubcommand::Sync { dir, force } => {
+            let config = get_config()?;
+            sync(&config, dir.as_deref(), *force)?;
+        }
+        RssSubcommand::Pack {
+            path,
+            git,
+            rev,
+            subdir,
+            output,
+            force,
+        } => {
+            let source = match (path, git) {
+                (_, Some(repo)) => PackSource::Git {
+                    repo,
+                    rev: rev.as_deref().unwrap_or("HEAD"),
+                    subdir: subdir.as_deref(),
+                },
+                (Some(path), None) => PackSource::Path(path),
+                (None, None) => unreachable!(),
+            };
+
+            let config = get_config()?;
+            pack(&config, source, output.as_deref(), *force)?;
+        }
+        RssSubcommand::History { file } => {
+            let config = get_config()?;
+            history(&config, file)?;
+        }
+        RssSubcommand::Show {
+            file,
+            rev,
+            patterns,
+            name,
+            extension,
+            all,
+            show_hidden,
+            raw,
+            line_numbers,
+        } => {
+            let config = get_config()?;
+            let filter = filter_from_args(
+                name.as_ref().map(|x| x.as_str()),
+                extension.as_ref().map(|x| x.as_str()),
+                patterns,
+                *all,
+            )?;
+            show(
+                &config,
+                file,
+                *rev,
+                filter,
+                CatOptions {
+                    show_hidden: *show_hidden,
+                    raw: *raw,
+                    line_numbers: *line_numbers,
+                },
+            )?;
+        }
+        RssSubcommand::Revert { file, revision } => {
+            let config = get_config()?;
+            revert(&config, file, *revision)?;
+        }
+        RssSubcommand::Diff { a, b } => {
+            let config = get_config()?;
+            diff(&config, a, b)?;
+        }
+        RssSubcommand::Textconv { file } => {
+            textconv(file)?;
+        }
+        RssSubcommand::MergeDriver {
+            ancestor,
+            current,
+            other,
+        } => {
+            let config = get_config()?;
+            // Git expects a non-zero exit code when there are conflicts
+            if !merge_driver(&config, ancestor, current, other)? {
+                return Err(
+                    "E248 Resolve conflict markers with `rss edit` - the binary has been removed"
+                        .to_owned(),
+                );
             }
         }
-        RssSubcommand::Extract { file } => {
+        RssSubcommand::GitSetup { global } => {
+            git_setup(*global)?;
+        }
+        RssSubcommand::Put {
+            file,
+            inner_path,
+            from,
+            rebuild,
+        } => {
             let config = get_config()?;
-            extract(&config, file)?;
+            put(
+                &config,
+                file,
+                inner_path,
+                from.as_ref().map(|x| x.as_str()),
+                *rebuild,
+            )?;
+        }
+        RssSubcommand::Rm {
+            file,
+            inner_path,
+            rebuild,
+        } => {
+            let config = get_config()?;
+            rm(&config, file, inner_path, *rebuild)?;
         }
-        RssSubcommand::Pack { directory } => {
+        RssSubcommand::Mv {
+            file,
+            from,
+            to,
+            rebuild,
+        } => {
+            let config = get_config()?;
+            mv(&config, file, from, to, *rebuild)?;
+        }
+        RssSubcommand::Restore { file, backup, list } => {
             let config = get_config()?;
-            pack(&config, directory)?;
+            restore(&config, file, *backup, *list)?;
         }
         RssSubcommand::Stats { file } => {
             let config = get_config()?;
             stats(&config, file)?;
         }
+        RssSubcommand::Info { file } => {
+            let config = get_config()?;
+            info(&config, file)?;
+        }
         RssSubcommand::Tree { file, show_hidden } => {
             let config = get_config()?;
             tree(&config, file, *show_hidden)?;
         }
         RssSubcommand::Cat {
             file,
+            patterns,
             name,
             extension,
             all,
             show_hidden,
+            raw,
+            line_numbers,
         } => {
             let config = get_config()?;
             cat(
@@ -120,10 +401,46 @@ fn wrapped_main() -> Result<(), String> {
                 file,
                 name.as_ref().map(|x| x.as_str()),
                 extension.as_ref().map(|x| x.as_str()),
+                patterns,
                 *all,
-                *show_hidden,
+                CatOptions {
+                    show_hidden: *show_hidden,
+                    raw: *raw,
+                    line_numbers: *line_numbers,
+                },
             )?;
         }
+        RssSubcommand::Export {
+            file,
+            to,
+            output,
+            target,
+            force,
+        } => {
+            let config = get_config()?;
+            export(
+                &config,
+                file,
+                *to,
+                output.as_deref(),
+                target.as_deref(),
+                *force,
+            )?;
+        }
+        RssSubcommand::Template { action } => {
+            let config = get_config()?;
+            match action {
+                TemplateAction::Ls => template_ls(&config)?,
+                TemplateAction::Add { name, path } => template_add(&config, name, path)?,
+                TemplateAction::Rm { name } => template_rm(&config, name)?,
+            }
+        }
+        RssSubcommand::Completions { shell } => {
+            completions(*shell)?;
+        }
+        RssSubcommand::Man { output } => {
+            man(output.as_deref())?;
+        }
     }
 
     Ok(())
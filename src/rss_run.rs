@@ -4,9 +4,9 @@ extern crate const_it;
 #[macro_use]
 extern crate static_assertions;
 
-use crate::shared::VERBOSE;
 use crate::shared::ctrl_c_handler::init_ctrl_c_handler;
 use crate::shared::wrapped_run::wrapped_run;
+use crate::shared::{JSON_OUTPUT, VERBOSE};
 use color_print::cprintln;
 
 mod shared;
@@ -14,12 +14,13 @@ mod shared;
 fn main() {
     let args: Vec<_> = std::env::args().collect();
     VERBOSE.set(false).unwrap();
+    JSON_OUTPUT.set(false).unwrap();
 
     // Not used for rss-run but may be in future
     init_ctrl_c_handler();
 
     if args.len() >= 2 {
-        if let Err(e) = wrapped_run(&args[1], &args[2..]) {
+        if let Err(e) = wrapped_run(&args[1], None, &args[2..]) {
             cprintln!("\n<red, bold>{e}</>");
         }
     } else {
@@ -1,19 +1,32 @@
+use std::path::PathBuf;
 use std::sync::OnceLock;
 
 pub mod args;
 pub mod config;
 pub mod edit;
+pub mod export;
 pub mod extract;
 // mod md_reader;
 pub mod cat;
+pub mod completions;
 pub mod ctrl_c_handler;
+pub mod diff;
+pub mod git;
+pub mod history;
+pub mod import;
+pub mod info;
 pub mod install;
 pub mod interruptable_command;
+pub mod modify;
 pub mod pack;
 pub mod recompile;
+pub mod restore;
 pub mod run;
+pub mod shell;
 pub mod stats;
 pub mod strip;
+pub mod sync;
+pub mod template;
 pub mod tree;
 pub mod util;
 pub mod wrapped_run;
@@ -22,3 +35,7 @@ pub const TARGET_TRIPLE: &str = env!("TARGET");
 pub const RS_SCRIPT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 pub static VERBOSE: OnceLock<bool> = OnceLock::new();
+/// Whether inspection commands should print JSON instead of text (`--format json`)
+pub static JSON_OUTPUT: OnceLock<bool> = OnceLock::new();
+/// Config file given with `--config`, applied over all other config layers
+pub static CONFIG_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
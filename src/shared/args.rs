@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use crate::shared::completions::{inner_name_completer, inner_path_completer, rss_file_completer};
+use clap::{Parser, Subcommand, ValueEnum};
 use derive_getters::Getters;
 
 #[derive(Parser, Debug, Getters)]
@@ -8,28 +9,110 @@ pub struct RssArgs {
     subcommand: RssSubcommand,
     #[arg(short, long, action, help = "Print additional information")]
     verbose: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = OutputFormat::Text,
+        help = "Output format of inspection commands (`stats`, `tree`, `cat`, ...)"
+    )]
+    format: OutputFormat,
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Config file to use over all other config layers (also edited by `rss config`)"
+    )]
+    config: Option<String>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Json,
 }
 
 #[derive(Subcommand, Debug)]
 pub enum RssSubcommand {
     #[command(visible_alias = "r", about = "Runs an rss file")]
     Run {
-        #[arg(help = "File to run")]
+        #[arg(help = "File to run", add = rss_file_completer())]
         file: String,
+        #[arg(long, help = "Binary to run (defaults to the default binary)")]
+        bin: Option<String>,
+        #[arg(long, conflicts_with = "bin", help = "Example to run")]
+        example: Option<String>,
         #[arg(trailing_var_arg = true, help = "Arguments to pass to the program")]
         args: Vec<String>,
     },
 
     #[command(visible_alias = "e", about = "Edit/create an rss file")]
     Edit {
-        #[arg(help = "File to edit")]
+        #[arg(help = "File to edit", add = rss_file_completer())]
         file: String,
+        #[arg(long, help = "Editor profile to use (see `rss config editors`)")]
+        editor: Option<String>,
+        #[arg(
+            long,
+            action,
+            conflicts_with = "editor",
+            help = "Opens only `src/main.rs` (or `--file`) in `$EDITOR`"
+        )]
+        quick: bool,
+        #[arg(
+            long = "file",
+            value_name = "PATH",
+            requires = "quick",
+            help = "File within the project to open with `--quick`",
+            add = inner_path_completer()
+        )]
+        quick_file: Option<String>,
     },
 
     #[command(visible_alias = "n", about = "Create an rss file")]
     New {
         #[arg(help = "File to create")]
         file: String,
+        #[arg(
+            short,
+            long,
+            help = "Template to create the project from (see `rss template ls`)"
+        )]
+        template: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "template",
+            help = "Single-file cargo script (or plain .rs file) to create the project from"
+        )]
+        from: Option<String>,
+        #[arg(long, help = "Editor profile to use (see `rss config editors`)")]
+        editor: Option<String>,
+    },
+
+    #[command(
+        about = "Opens a shell in an rss file's project, building and saving it when the shell exits"
+    )]
+    Shell {
+        #[arg(help = "File to open a shell in", add = rss_file_completer())]
+        file: String,
+    },
+
+    #[command(about = "Creates an rss file from a single-file cargo script (or plain .rs file)")]
+    Import {
+        #[arg(help = "Script to import")]
+        script: String,
+        #[arg(
+            short,
+            long,
+            help = "Rss file to create (defaults to the script's name)"
+        )]
+        output: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Overwrite the rss file if it already exists"
+        )]
+        force: bool,
     },
 
     #[command(
@@ -37,16 +120,29 @@ pub enum RssSubcommand {
         about = "Install an rss file globally with `cargo install`"
     )]
     Install {
-        #[arg(help = "File to install")]
+        #[arg(help = "File to install", add = rss_file_completer())]
         file: String,
     },
 
-    #[command(visible_alias = "conf", about = "Change config options")]
+    #[command(
+        visible_alias = "conf",
+        about = "Change config options",
+        args_conflicts_with_subcommands = true
+    )]
     Config {
         #[arg(short, long, action, help = "Reset config to default")]
         reset: bool,
         #[arg(short, long, action, help = "Outputs the config file location")]
         r#where: bool,
+        #[arg(
+            long,
+            action,
+            conflicts_with_all = ["reset", "where"],
+            help = "Shows which config layer set each value"
+        )]
+        show_origin: bool,
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
     },
 
     #[command(
@@ -54,8 +150,10 @@ pub enum RssSubcommand {
         about = "Strips the compiled binary from an rss file"
     )]
     Strip {
-        #[arg(help = "File to strip")]
+        #[arg(help = "File to strip", add = rss_file_completer())]
         file: String,
+        #[arg(long, action, help = "Also remove the edit history")]
+        history: bool,
     },
 
     #[command(
@@ -63,14 +161,95 @@ pub enum RssSubcommand {
         about = "Extracts the Rust project from the rss file"
     )]
     Extract {
-        #[arg(help = "File to extract")]
+        #[arg(help = "File to extract", add = rss_file_completer())]
         file: String,
+        #[arg(
+            short,
+            long,
+            help = "Directory to extract to (defaults to the file name without extension)"
+        )]
+        output: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Extract into an existing directory, overwriting files"
+        )]
+        force: bool,
+        #[arg(
+            long,
+            value_name = "GLOB",
+            help = "Only extract files matching a glob pattern (can be repeated)"
+        )]
+        only: Vec<String>,
+        #[arg(
+            long,
+            action,
+            help = "Also write the compiled binary next to the extracted project"
+        )]
+        with_binary: bool,
+        #[arg(
+            long,
+            action,
+            conflicts_with = "only",
+            help = "Link the extracted directory to the rss file so changes can be written back with `rss sync`"
+        )]
+        link: bool,
+    },
+
+    #[command(
+        about = "Rebuilds a directory extracted with `rss extract --link` and writes it back to its rss file"
+    )]
+    Sync {
+        #[arg(help = "Linked directory (defaults to the current directory)")]
+        dir: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Write back even if the rss file has changed since it was extracted"
+        )]
+        force: bool,
     },
 
     #[command(visible_alias = "p", about = "Creates an rss file from a Rust project")]
     Pack {
-        #[arg(help = "Rust project folder")]
-        directory: String,
+        #[arg(
+            required_unless_present = "git",
+            help = "Rust project folder or .tar.gz/.zip/.crate archive"
+        )]
+        path: Option<String>,
+        #[arg(
+            long,
+            conflicts_with = "path",
+            help = "Local git repository to pack a revision of"
+        )]
+        git: Option<String>,
+        #[arg(
+            long,
+            requires = "git",
+            help = "Git revision to pack (defaults to HEAD)"
+        )]
+        rev: Option<String>,
+        #[arg(
+            long,
+            requires = "git",
+            help = "Directory within the git repository to pack"
+        )]
+        subdir: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "Rss file to create (defaults to the project's name)"
+        )]
+        output: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Overwrite the rss file if it already exists"
+        )]
+        force: bool,
     },
 
     #[command(
@@ -78,21 +257,29 @@ pub enum RssSubcommand {
         about = "Recompile the compiled binary for an rss file"
     )]
     Recompile {
-        #[arg(help = "File to recompile")]
+        #[arg(help = "File to recompile", add = rss_file_completer())]
         file: String,
     },
 
     #[command(visible_alias = "stat", about = "Prints statistics about an rss file")]
     Stats {
-        #[arg(help = "File to get statistics of")]
+        #[arg(help = "File to get statistics of", add = rss_file_completer())]
+        file: String,
+    },
+
+    #[command(
+        about = "Prints the package information, dependencies and README of the project in an rss file"
+    )]
+    Info {
+        #[arg(help = "File to describe", add = rss_file_completer())]
         file: String,
     },
 
     #[command(visible_alias = "t", about = "Prints the file tree within an rss file")]
     Tree {
-        #[arg(help = "File to print tree of")]
+        #[arg(help = "File to print tree of", add = rss_file_completer())]
         file: String,
-        #[arg(help = "Show hidden files")]
+        #[arg(short = 'H', long, action, help = "Show hidden files")]
         show_hidden: bool,
     },
 
@@ -101,9 +288,20 @@ pub enum RssSubcommand {
         about = "Prints the contents of files within an rss file"
     )]
     Cat {
-        #[arg(help = "Rss file to print file contents of")]
+        #[arg(help = "Rss file to print file contents of", add = rss_file_completer())]
         file: String,
-        #[arg(short, long, action, help = "Filter files by name")]
+        #[arg(
+            help = "Glob patterns to filter files by (e.g. `src/**/*.rs`, `Cargo.*`)",
+            add = inner_path_completer()
+        )]
+        patterns: Vec<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Filter files by name",
+            add = inner_name_completer()
+        )]
         name: Option<String>,
         #[arg(short, long, action, help = "Filter files by extension")]
         extension: Option<String>,
@@ -114,10 +312,293 @@ pub enum RssSubcommand {
             help = "Prints all files (default is only .rs files)"
         )]
         all: bool,
-        #[arg(help = "Print hidden files")]
+        #[arg(short = 'H', long, action, help = "Print hidden files")]
         show_hidden: bool,
+        #[arg(
+            short,
+            long,
+            action,
+            conflicts_with = "line_numbers",
+            help = "Print file contents exactly, without file names or decoration"
+        )]
+        raw: bool,
+        #[arg(short, long, action, help = "Print line numbers")]
+        line_numbers: bool,
+    },
+
+    #[command(about = "Restores an rss file from one of its automatic backups")]
+    Restore {
+        #[arg(help = "File to restore", add = rss_file_completer())]
+        file: String,
+        #[arg(
+            short,
+            long,
+            default_value_t = 1,
+            help = "Backup to restore (1 is the most recent)"
+        )]
+        backup: usize,
+        #[arg(short, long, action, help = "List available backups")]
+        list: bool,
+    },
+
+    #[command(
+        visible_alias = "hist",
+        about = "Lists the edit history of an rss file"
+    )]
+    History {
+        #[arg(help = "File to list the history of", add = rss_file_completer())]
+        file: String,
+    },
+
+    #[command(about = "Prints the contents of files from a revision in the history")]
+    Show {
+        #[arg(help = "Rss file to print file contents of", add = rss_file_completer())]
+        file: String,
+        #[arg(long, help = "Revision to show (defaults to the latest)")]
+        rev: Option<u64>,
+        #[arg(
+            help = "Glob patterns to filter files by (e.g. `src/**/*.rs`, `Cargo.*`)",
+            add = inner_path_completer()
+        )]
+        patterns: Vec<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Filter files by name",
+            add = inner_name_completer()
+        )]
+        name: Option<String>,
+        #[arg(short, long, action, help = "Filter files by extension")]
+        extension: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Prints all files (default is only .rs files)"
+        )]
+        all: bool,
+        #[arg(short = 'H', long, action, help = "Print hidden files")]
+        show_hidden: bool,
+        #[arg(
+            short,
+            long,
+            action,
+            conflicts_with = "line_numbers",
+            help = "Print file contents exactly, without file names or decoration"
+        )]
+        raw: bool,
+        #[arg(short, long, action, help = "Print line numbers")]
+        line_numbers: bool,
+    },
+
+    #[command(about = "Reverts the project in an rss file to a revision in the history")]
+    Revert {
+        #[arg(help = "File to revert", add = rss_file_completer())]
+        file: String,
+        #[arg(help = "Revision to revert to")]
+        revision: u64,
+    },
+
+    #[command(
+        visible_alias = "d",
+        about = "Prints the differences between two rss files (or an rss file and a directory)"
+    )]
+    Diff {
+        #[arg(help = "Rss file or project directory to compare from", add = rss_file_completer())]
+        a: String,
+        #[arg(help = "Rss file or project directory to compare to", add = rss_file_completer())]
+        b: String,
+    },
+
+    #[command(about = "Prints a plain text rendering of an rss file (for `git diff`)")]
+    Textconv {
+        #[arg(help = "Rss file to render", add = rss_file_completer())]
+        file: String,
+    },
+
+    #[command(about = "Three-way merges rss files (used as a git merge driver)")]
+    MergeDriver {
+        #[arg(help = "Common ancestor (%O)")]
+        ancestor: String,
+        #[arg(help = "Current version, overwritten with the result (%A)")]
+        current: String,
+        #[arg(help = "Other branch's version (%B)")]
+        other: String,
+    },
+
+    #[command(about = "Configures the current git repository to diff and merge rss files")]
+    GitSetup {
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Configure git globally instead (global git config and attributes file), without needing a repository"
+        )]
+        global: bool,
+    },
+
+    #[command(about = "Writes a file within an rss file's project (from stdin by default)")]
+    Put {
+        #[arg(help = "Rss file to modify", add = rss_file_completer())]
+        file: String,
+        #[arg(
+            help = "Path of the file within the project (e.g. `src/main.rs`)",
+            add = inner_path_completer()
+        )]
+        inner_path: String,
+        #[arg(long, help = "Local file to read the contents from instead of stdin")]
+        from: Option<String>,
+        #[arg(long, action, help = "Rebuild the binary (otherwise it is removed)")]
+        rebuild: bool,
+    },
+
+    #[command(about = "Removes a file or directory from an rss file's project")]
+    Rm {
+        #[arg(help = "Rss file to modify", add = rss_file_completer())]
+        file: String,
+        #[arg(help = "Path of the file or directory within the project", add = inner_path_completer())]
+        inner_path: String,
+        #[arg(long, action, help = "Rebuild the binary (otherwise it is removed)")]
+        rebuild: bool,
+    },
+
+    #[command(about = "Moves a file or directory within an rss file's project")]
+    Mv {
+        #[arg(help = "Rss file to modify", add = rss_file_completer())]
+        file: String,
+        #[arg(help = "Current path within the project", add = inner_path_completer())]
+        from: String,
+        #[arg(help = "New path within the project")]
+        to: String,
+        #[arg(long, action, help = "Rebuild the binary (otherwise it is removed)")]
+        rebuild: bool,
+    },
+
+    #[command(about = "Exports the binary or project from an rss file")]
+    Export {
+        #[arg(help = "File to export from", add = rss_file_completer())]
+        file: String,
+        #[arg(
+            short,
+            long,
+            value_enum,
+            value_name = "FORMAT",
+            default_value_t = ExportFormat::Bin,
+            help = "What to export"
+        )]
+        to: ExportFormat,
+        #[arg(
+            short,
+            long,
+            help = "File to write to (defaults to next to the rss file)"
+        )]
+        output: Option<String>,
+        #[arg(
+            long,
+            help = "Target triple of the binary to export (defaults to the current one)"
+        )]
+        target: Option<String>,
+        #[arg(
+            short,
+            long,
+            action,
+            help = "Overwrite the output file if it already exists"
+        )]
+        force: bool,
+    },
+
+    #[command(about = "Manages project templates for `rss new`")]
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
+    #[command(
+        about = "Prints a script that sets up completions for a shell (e.g. `source <(rss completions bash)`)"
+    )]
+    Completions {
+        #[arg(help = "Shell to set up completions for")]
+        shell: CompletionShell,
+    },
+
+    #[command(about = "Prints the man page (or writes a page per subcommand with `--output`)")]
+    Man {
+        #[arg(short, long, help = "Directory to write man pages to")]
+        output: Option<String>,
     },
 
     #[command(about = "Read the README")]
     Readme,
 }
+
+#[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ExportFormat {
+    /// The compiled binary
+    Bin,
+    /// The project as a .tar.gz archive
+    #[value(name = "tar.gz")]
+    TarGz,
+    /// The project as a .zip archive
+    Zip,
+    /// A single-file cargo script (only for projects with just a main.rs)
+    CargoScript,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateAction {
+    #[command(visible_alias = "list", about = "Lists templates")]
+    Ls,
+
+    #[command(about = "Adds (or replaces) a template")]
+    Add {
+        #[arg(help = "Name of the template")]
+        name: String,
+        #[arg(help = "Project directory or rss file to use as the template")]
+        path: String,
+    },
+
+    #[command(visible_alias = "remove", about = "Removes a template")]
+    Rm {
+        #[arg(help = "Name of the template")]
+        name: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    #[command(about = "Prints a config value (e.g. `rss config get backup_count`)")]
+    Get {
+        #[arg(help = "Key of the value, with `.` separating nested keys")]
+        key: String,
+    },
+
+    #[command(about = "Sets a value in the config file (e.g. `rss config set backup_count 5`)")]
+    Set {
+        #[arg(help = "Key of the value, with `.` separating nested keys")]
+        key: String,
+        #[arg(help = "Value to set, as JSON (plain strings don't need quotes)")]
+        value: String,
+    },
+
+    #[command(about = "Removes a value from the config file so its default is used")]
+    Unset {
+        #[arg(help = "Key of the value, with `.` separating nested keys")]
+        key: String,
+    },
+
+    #[command(about = "Prints a JSON Schema for config files")]
+    Schema,
+
+    #[command(about = "Lists editor profiles and which one `rss edit` will use")]
+    Editors,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CompletionShell {
+    Bash,
+    Zsh,
+    Fish,
+    Elvish,
+    Powershell,
+}
@@ -1,8 +1,8 @@
 use crate::shared::config::Config;
 use crate::shared::util::auto_append_rss;
 use crate::shared::util::file_contents::FileContents;
-use crate::shared::util::zip::{Filter, cat_files};
-use color_print::cprintln;
+use crate::shared::util::zip::{CatOptions, Filter, cat_files};
+use color_print::ceprintln;
 use std::path::{Path, PathBuf};
 
 pub fn cat<P: AsRef<Path>>(
@@ -10,8 +10,9 @@ pub fn cat<P: AsRef<Path>>(
     path: P,
     name: Option<&str>,
     extension: Option<&str>,
+    patterns: &[String],
     all: bool,
-    show_hidden: bool,
+    options: CatOptions,
 ) -> Result<(), String> {
     let path = if path.as_ref().is_file() {
         PathBuf::from(path.as_ref())
@@ -19,17 +20,43 @@ pub fn cat<P: AsRef<Path>>(
         auto_append_rss(path, config)
     };
 
-    if name.is_some() && extension.is_some() {
-        return Err("E83 Both `name` and `extension` flags cannot be used together.".to_string());
+    let filter = filter_from_args(name, extension, patterns, all)?;
+
+    let path_contents = FileContents::from_path(&path)?
+        .ok_or(format!("E81 File contents not found: {:?}", path.as_path()))?;
+
+    cat_files(path_contents.zipped_contents(), filter, options)?;
+
+    Ok(())
+}
+
+pub fn filter_from_args(
+    name: Option<&str>,
+    extension: Option<&str>,
+    patterns: &[String],
+    all: bool,
+) -> Result<Filter, String> {
+    if [name.is_some(), extension.is_some(), !patterns.is_empty()]
+        .iter()
+        .filter(|x| **x)
+        .count()
+        > 1
+    {
+        return Err(
+            "E83 Only one of the `name` flag, `extension` flag or patterns can be used."
+                .to_string(),
+        );
     }
 
-    if (extension.is_some() || name.is_some()) && all {
-        cprintln!(
-            "<yellow, bold>Using the `all` flag is redundant when using the `extension` or `name` flag.</>"
+    if (extension.is_some() || name.is_some() || !patterns.is_empty()) && all {
+        ceprintln!(
+            "<yellow, bold>Using the `all` flag is redundant when using the `extension` or `name` flag or patterns.</>"
         );
     }
 
-    let filter = if let Some(name) = name {
+    Ok(if !patterns.is_empty() {
+        Filter::from_globs(patterns)?
+    } else if let Some(name) = name {
         Filter::Name(name.to_string())
     } else if let Some(extension) = extension {
         Filter::Extension(extension.to_string())
@@ -37,12 +64,5 @@ pub fn cat<P: AsRef<Path>>(
         Filter::None
     } else {
         Filter::Extension("rs".to_string())
-    };
-
-    let path_contents = FileContents::from_path(&path)?
-        .ok_or(format!("E81 File contents not found: {:?}", path.as_path()))?;
-
-    cat_files(path_contents.zipped_contents(), filter, show_hidden)?;
-
-    Ok(())
+    })
 }
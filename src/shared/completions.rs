@@ -0,0 +1,137 @@
+use crate::shared::VERBOSE;
+use crate::shared::args::{CompletionShell, RssArgs};
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::read_zip_entries;
+use clap::CommandFactory;
+use clap_complete::CompleteEnv;
+use clap_complete::engine::{ArgValueCompleter, CompletionCandidate, PathCompleter};
+use clap_complete::env::{Bash, Elvish, EnvCompleter, Fish, Powershell, Zsh};
+use color_print::cprintln;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+use std::io::stdout;
+use std::path::{Path, PathBuf};
+
+/// Environment variable the shell scripts from `rss completions` set when asking for completions
+const COMPLETE_VAR: &str = "COMPLETE";
+
+const BIN_NAME: &str = "rss";
+
+fn command() -> clap::Command {
+    RssArgs::command().name(BIN_NAME)
+}
+
+/// Answers completion requests from the scripts written by `rss completions` (exits if this run
+/// is one)
+pub fn handle_completion_request() {
+    CompleteEnv::with_factory(command)
+        .var(COMPLETE_VAR)
+        .bin(BIN_NAME)
+        .complete();
+}
+
+/// Completes `.rss` files (and directories containing them)
+pub fn rss_file_completer() -> ArgValueCompleter {
+    ArgValueCompleter::new(
+        PathCompleter::any().filter(|p| p.extension().is_some_and(|e| e == "rss")),
+    )
+}
+
+/// Completes paths within the project of the rss file on the command line
+pub fn inner_path_completer() -> ArgValueCompleter {
+    ArgValueCompleter::new(|current: &OsStr| complete_inner(current, false))
+}
+
+/// Completes names of files within the project of the rss file on the command line
+pub fn inner_name_completer() -> ArgValueCompleter {
+    ArgValueCompleter::new(|current: &OsStr| complete_inner(current, true))
+}
+
+/// Finds the rss file being completed for. The shell passes the command line after `--`, with the
+/// value being completed last.
+fn rss_file_from_args() -> Option<PathBuf> {
+    let args = std::env::args_os()
+        .skip_while(|a| a != "--")
+        .skip(1)
+        .collect::<Vec<_>>();
+    // Skip the binary and subcommand names
+    args.get(2..args.len().saturating_sub(1))?
+        .iter()
+        .filter(|a| !a.to_string_lossy().starts_with('-'))
+        .flat_map(|a| [PathBuf::from(a), PathBuf::from(a).with_extension("rss")])
+        .find(|p| p.is_file())
+}
+
+fn complete_inner(current: &OsStr, names_only: bool) -> Vec<CompletionCandidate> {
+    // Completion requests run before arguments are parsed
+    VERBOSE.get_or_init(|| false);
+
+    let Some(path) = rss_file_from_args() else {
+        return Vec::new();
+    };
+    let Ok(Some(file_contents)) = FileContents::from_path(&path) else {
+        return Vec::new();
+    };
+    let Ok(entries) = read_zip_entries(file_contents.zipped_contents()) else {
+        return Vec::new();
+    };
+
+    let current = current.to_string_lossy();
+    let candidates = entries
+        .iter()
+        .filter(|e| !e.directory)
+        .filter_map(|e| {
+            if names_only {
+                Path::new(&e.name)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+            } else {
+                Some(e.name.clone())
+            }
+        })
+        .filter(|c| c.starts_with(current.as_ref()))
+        .collect::<BTreeSet<_>>();
+    candidates
+        .into_iter()
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+/// Prints the script that registers completions with `shell`
+pub fn completions(shell: CompletionShell) -> Result<(), String> {
+    let completer: &dyn EnvCompleter = match shell {
+        CompletionShell::Bash => &Bash,
+        CompletionShell::Zsh => &Zsh,
+        CompletionShell::Fish => &Fish,
+        CompletionShell::Elvish => &Elvish,
+        CompletionShell::Powershell => &Powershell,
+    };
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("E231 Failed to find the rss executable: {e}"))?;
+
+    completer
+        .write_registration(
+            COMPLETE_VAR,
+            BIN_NAME,
+            BIN_NAME,
+            &exe.to_string_lossy(),
+            &mut stdout(),
+        )
+        .map_err(|e| format!("E232 Failed to write completions: {e}"))
+}
+
+/// Prints the man page for rss, or writes pages for rss and each subcommand to `output`
+pub fn man(output: Option<&str>) -> Result<(), String> {
+    let Some(output) = output else {
+        return clap_mangen::Man::new(command())
+            .render(&mut stdout())
+            .map_err(|e| format!("E233 Failed to write man page: {e}"));
+    };
+
+    std::fs::create_dir_all(output)
+        .map_err(|e| format!("E234 Failed to create directory '{output}': {e}"))?;
+    clap_mangen::generate_to(command(), output)
+        .map_err(|e| format!("E233 Failed to write man page: {e}"))?;
+    cprintln!("Wrote man pages to <green, bold>'{}'</>", output);
+    Ok(())
+}
@@ -1,27 +1,80 @@
 pub mod edit_command;
+pub mod editors;
 
-use crate::shared::VERBOSE;
+use crate::shared::util::print_json;
+use crate::shared::{CONFIG_OVERRIDE, JSON_OUTPUT, VERBOSE};
 use crate::{print_task_start, println_task_duration, time};
-use color_print::cprintln;
+use color_print::{ceprintln, cprintln};
+use colored_json::ToColoredJson;
 use derive_getters::Getters;
 use directories::BaseDirs;
-use edit_command::EditCommand;
-use serde::{Deserialize, Serialize};
+use edit_command::{EditCommand, EditContext};
+use editors::choose_editor;
+use schemars::{JsonSchema, schema_for};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fmt::{Display, Formatter};
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write, stdin, stdout};
 use std::path::{Path, PathBuf};
 use std::time::Instant;
 
-#[derive(Serialize, Deserialize, Debug, Getters)]
+/// Name of the per-project config file, discovered in the current directory and its parents
+pub const PROJECT_CONFIG_FILE: &str = ".rss-config.json";
+
+/// Prefix of the environment variables overriding config values, e.g. `RSS_BACKUP_COUNT`
+const ENV_PREFIX: &str = "RSS_";
+
+/// Environment variable choosing the editor profile to use
+const EDITOR_ENV: &str = "RSS_EDITOR";
+
+/// Key of the JSON Schema editors use for autocompletion, which isn't a config value
+const SCHEMA_KEY: &str = "$schema";
+
+#[derive(Serialize, Deserialize, Debug, Getters, JsonSchema)]
 #[serde(default)]
+#[schemars(title = "rss config")]
 pub struct Config {
+    /// Command used to open the config file with `rss config`
     config_edit_command: EditCommand,
+    /// Command used to edit projects - it must not exit until editing is finished
     rust_project_edit_command_blocking: EditCommand,
+    /// Named commands used to edit projects, chosen with `rss edit --editor <name>` or `RSS_EDITOR`
+    editors: BTreeMap<String, EditCommand>,
+    /// Editor profile used instead of `rust_project_edit_command_blocking` by default
+    default_editor: Option<String>,
+    /// Editor profile used automatically in SSH sessions
+    ssh_editor: Option<String>,
+    /// Build binaries in debug mode instead of release mode
     use_debug_mode: bool,
+    /// Never store compiled binaries in rss files
     never_save_binary: bool,
+    /// Append `.rss` to file arguments that don't exist
     auto_append_rss_ext: bool,
+    /// Make rss files executable (Linux)
     make_rss_executable_linux: bool,
+    /// Number of backups kept when an rss file is overwritten (`0` disables backups)
+    backup_count: usize,
+    /// Number of revisions of edit history kept in rss files (`0` disables history)
+    history_length: usize,
+    /// Ask for a message describing each edit when history is enabled
+    prompt_history_message: bool,
+    /// Templates for `rss new --template`, by name
+    templates: BTreeMap<String, String>,
+    /// The layer each value was set by
+    #[serde(skip)]
+    #[getter(skip)]
+    origins: BTreeMap<String, ConfigOrigin>,
+    /// Editor profile chosen with `--editor`
+    #[serde(skip)]
+    #[getter(skip)]
+    selected_editor: Option<String>,
+    /// Command used instead of any editor profile for this run, e.g. for `rss edit --quick`
+    #[serde(skip)]
+    #[getter(skip)]
+    edit_command_override: Option<EditCommand>,
 }
 
 impl Default for Config {
@@ -29,26 +82,121 @@ impl Default for Config {
         Config {
             config_edit_command: Default::default(),
             rust_project_edit_command_blocking: Default::default(),
+            editors: BTreeMap::new(),
+            default_editor: None,
+            ssh_editor: None,
             use_debug_mode: false,
             never_save_binary: false,
             auto_append_rss_ext: true,
             make_rss_executable_linux: true,
+            backup_count: 3,
+            history_length: 0,
+            prompt_history_message: true,
+            templates: BTreeMap::new(),
+            origins: BTreeMap::new(),
+            selected_editor: None,
+            edit_command_override: None,
         }
     }
 }
 
 impl Config {
-    #[allow(dead_code)]
-    pub fn save(&self) -> Result<(), String> {
-        let config_file = get_config_path()?;
-        let json = serde_json::to_string_pretty(&self)
-            .map_err(|_| "E13 Failed to serialise config".to_owned())?;
-        fs::write(&config_file, json).map_err(|_| "E14 Failed to write config file".to_owned())?;
-        Ok(())
+    /// Uses the editor profile `name` for this run
+    pub fn select_editor(&mut self, name: Option<String>) {
+        self.selected_editor = name;
+    }
+
+    /// Uses `command` to edit projects for this run
+    pub fn override_edit_command(&mut self, command: EditCommand) {
+        self.edit_command_override = Some(command);
+    }
+
+    /// The editor profile to use, and what chose it
+    fn editor_profile(&self) -> Option<(String, &'static str)> {
+        if let Some(name) = &self.selected_editor {
+            return Some((name.clone(), "--editor"));
+        }
+        if let Ok(name) = std::env::var(EDITOR_ENV)
+            && !name.is_empty()
+        {
+            return Some((name, "$RSS_EDITOR"));
+        }
+        if in_ssh_session()
+            && let Some(name) = &self.ssh_editor
+        {
+            return Some((name.clone(), "ssh_editor"));
+        }
+        self.default_editor
+            .as_ref()
+            .map(|name| (name.clone(), "default_editor"))
+    }
+
+    /// The command used to edit projects - the chosen editor profile, or
+    /// `rust_project_edit_command_blocking` if there isn't one
+    pub fn project_edit_command(&self) -> Result<&EditCommand, String> {
+        if let Some(command) = &self.edit_command_override {
+            return Ok(command);
+        }
+        match self.editor_profile() {
+            Some((name, source)) => self.editors.get(&name).ok_or(format!(
+                "E243 Editor profile '{name}' (from {source}) not found - list profiles with `rss config editors`"
+            )),
+            None => Ok(&self.rust_project_edit_command_blocking),
+        }
+    }
+
+    /// The layer each value was set by
+    pub fn origins(&self) -> &BTreeMap<String, ConfigOrigin> {
+        &self.origins
     }
 }
 
-pub fn get_config_path() -> Result<PathBuf, String> {
+fn in_ssh_session() -> bool {
+    ["SSH_CONNECTION", "SSH_CLIENT", "SSH_TTY"]
+        .iter()
+        .any(|v| std::env::var_os(v).is_some())
+}
+
+/// A layer of the config, from lowest to highest precedence
+#[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(tag = "layer", content = "source", rename_all = "snake_case")]
+pub enum ConfigOrigin {
+    Default,
+    System(PathBuf),
+    User(PathBuf),
+    Project(PathBuf),
+    Environment(String),
+    Override(PathBuf),
+}
+
+impl ConfigOrigin {
+    /// The file this layer was read from
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ConfigOrigin::System(p)
+            | ConfigOrigin::User(p)
+            | ConfigOrigin::Project(p)
+            | ConfigOrigin::Override(p) => Some(p),
+            ConfigOrigin::Default | ConfigOrigin::Environment(_) => None,
+        }
+    }
+}
+
+impl Display for ConfigOrigin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigOrigin::Default => write!(f, "default"),
+            ConfigOrigin::System(p) => write!(f, "system file '{}'", p.to_string_lossy()),
+            ConfigOrigin::User(p) => write!(f, "user file '{}'", p.to_string_lossy()),
+            ConfigOrigin::Project(p) => write!(f, "project file '{}'", p.to_string_lossy()),
+            ConfigOrigin::Environment(v) => write!(f, "environment variable '{v}'"),
+            ConfigOrigin::Override(p) => write!(f, "--config '{}'", p.to_string_lossy()),
+        }
+    }
+}
+
+/// The user config file
+fn get_user_config_path() -> Result<PathBuf, String> {
     let Some(config_dir) = BaseDirs::new().map(|bd| bd.config_dir().to_owned()) else {
         return Err("E03 Failed to get config directory".to_owned());
     };
@@ -56,55 +204,242 @@ pub fn get_config_path() -> Result<PathBuf, String> {
     Ok(config_dir.join("rss-config.json"))
 }
 
+/// The config file shared by all users
+fn get_system_config_path() -> Option<PathBuf> {
+    #[cfg(unix)]
+    return Some(PathBuf::from("/etc/rss-config.json"));
+    #[cfg(windows)]
+    return std::env::var_os("ProgramData").map(|p| PathBuf::from(p).join("rss-config.json"));
+}
+
+/// The nearest `.rss-config.json` in the current directory or its parents
+fn find_project_config() -> Option<PathBuf> {
+    let cwd = std::env::current_dir().ok()?;
+    cwd.ancestors()
+        .map(|d| d.join(PROJECT_CONFIG_FILE))
+        .find(|p| p.is_file())
+}
+
+/// The config file that is edited, reset and saved to - the `--config` file if one was given,
+/// otherwise the user config file
+pub fn get_config_path() -> Result<PathBuf, String> {
+    Ok(writable_layer()?.path().unwrap().to_owned())
+}
+
+/// The layer of the config file that is edited, reset and saved to
+fn writable_layer() -> Result<ConfigOrigin, String> {
+    match CONFIG_OVERRIDE.get() {
+        Some(path) => Ok(ConfigOrigin::Override(path.clone())),
+        None => Ok(ConfigOrigin::User(get_user_config_path()?)),
+    }
+}
+
+/// Checks the values of a layer against `Config`, returning the keys that aren't config values
+fn check_layer<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, serde_path_to_error::Error<D::Error>> {
+    let mut unknown = Vec::new();
+    let mut on_unknown = |path: serde_ignored::Path| unknown.push(path.to_string());
+    serde_path_to_error::deserialize::<_, Config>(serde_ignored::Deserializer::new(
+        deserializer,
+        &mut on_unknown,
+    ))?;
+    unknown.retain(|k| k != SCHEMA_KEY);
+    Ok(unknown)
+}
+
+/// Describes an invalid value, including its key
+fn layer_error<E: Display>(
+    code: &str,
+    origin: &ConfigOrigin,
+    error: serde_path_to_error::Error<E>,
+) -> String {
+    let key = error.path().to_string();
+    if key == "." {
+        format!("{code} Invalid config in {origin}: {}", error.inner())
+    } else {
+        format!(
+            "{code} Invalid config value '{key}' in {origin}: {}",
+            error.inner()
+        )
+    }
+}
+
+fn warn_unknown_keys(origin: &ConfigOrigin, unknown: &[String]) {
+    for key in unknown {
+        ceprintln!(
+            "<yellow, bold>Ignoring unknown config key '{}' in {}</>",
+            key,
+            origin
+        );
+    }
+}
+
+/// Reads a config file, returning its values and the keys that aren't config values
+fn read_layer(
+    path: &Path,
+    origin: &ConfigOrigin,
+) -> Result<(Map<String, Value>, Vec<String>), String> {
+    let mut file = File::open(path).map_err(|_| {
+        format!(
+            "E15 Failed to open config file '{}'",
+            path.to_string_lossy()
+        )
+    })?;
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(|_| {
+        format!(
+            "E16 Failed to read config file '{}'",
+            path.to_string_lossy()
+        )
+    })?;
+    let unknown = check_layer(&mut serde_json::Deserializer::from_str(&contents))
+        .map_err(|e| layer_error("E17", origin, e))?;
+    let values = serde_json::from_str(&contents)
+        .map_err(|e| format!("E17 Invalid config in {origin}: {e}"))?;
+    Ok((values, unknown))
+}
+
+/// Reads the config file that is written to, which may not exist yet
+fn read_writable_layer(origin: &ConfigOrigin) -> Result<Map<String, Value>, String> {
+    let path = origin.path().unwrap();
+    if !path.is_file() {
+        return Ok(Map::new());
+    }
+    let (values, unknown) = read_layer(path, origin)?;
+    warn_unknown_keys(origin, &unknown);
+    Ok(values)
+}
+
+fn write_layer(path: &Path, values: &Map<String, Value>) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(values)
+        .map_err(|_| "E13 Failed to serialise config".to_owned())?;
+    fs::write(path, json).map_err(|_| "E14 Failed to write config file".to_owned())
+}
+
+/// Reads `RSS_*` environment variables for each config value. Values are JSON, or plain strings.
+fn read_env_layers(keys: &[String]) -> Vec<(ConfigOrigin, Map<String, Value>)> {
+    keys.iter()
+        .filter_map(|key| {
+            let var = format!("{ENV_PREFIX}{}", key.to_uppercase());
+            let raw = std::env::var(&var).ok()?;
+            let value = serde_json::from_str(&raw).unwrap_or(Value::String(raw));
+            Some((
+                ConfigOrigin::Environment(var),
+                Map::from_iter([(key.clone(), value)]),
+            ))
+        })
+        .collect()
+}
+
+/// Creates the user config file with default values on first run
+fn create_user_config(config_file: &Path) -> Result<(), String> {
+    println!(
+        "\nConfig file not found. Creating default at '{}'.",
+        config_file.as_os_str().to_string_lossy()
+    );
+    let config = match choose_editor() {
+        Some(editor) => Config {
+            config_edit_command: editor.clone(),
+            rust_project_edit_command_blocking: editor,
+            ..Default::default()
+        },
+        None => {
+            cprintln!(
+                "<yellow, bold>[!] No other editor found, so VS Code will be used - change the editor in the config if it isn't in path!</>"
+            );
+            print!("Press enter to continue...");
+            stdout().flush().ok();
+            let mut t = String::new();
+            stdin().read_line(&mut t).ok();
+            Config::default()
+        }
+    };
+    let json = serde_json::to_string_pretty(&config)
+        .map_err(|_| "E18 Failed to serialize config".to_owned())?;
+
+    time!(
+        "Writing config",
+        false,
+        fs::write(config_file, json).map_err(|_| "E19 Failed to write config file".to_owned())?;
+    );
+    Ok(())
+}
+
 pub fn get_config() -> Result<Config, String> {
-    let mut cancel_time = false;
+    let user_config_file = get_user_config_path()?;
+    if CONFIG_OVERRIDE.get().is_none() && !user_config_file.exists() {
+        create_user_config(&user_config_file)?;
+    }
+
     if *VERBOSE.get().unwrap() {
         print_task_start!("Fetching config");
     }
-
     let start = Instant::now();
 
-    let config_file = get_config_path()?;
+    let Value::Object(mut values) = serde_json::to_value(Config::default())
+        .map_err(|_| "E18 Failed to serialize config".to_owned())?
+    else {
+        return Err("E18 Failed to serialize config".to_owned());
+    };
+    let keys = values.keys().cloned().collect::<Vec<_>>();
 
-    let r = Ok(if Path::new(&config_file).exists() {
-        let mut file =
-            File::open(&config_file).map_err(|_| "E15 Failed to open config file".to_owned())?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)
-            .map_err(|_| "E16 Failed to read config file".to_owned())?;
-        serde_json::from_str(&contents).map_err(|_| "E17 Failed to parse config file".to_owned())?
-    } else {
-        cancel_time = true;
-        println!(
-            "\nConfig file not found. Creating default at '{}'.",
-            get_config_path()?.as_os_str().to_string_lossy()
-        );
-        cprintln!(
-            "<yellow, bold>[!] Change the editor in the config if you do not have VS Code in path!</>"
-        );
-        print!("Press enter to continue...");
-        stdout().flush().ok();
-        let mut t = String::new();
-        stdin().read_line(&mut t).ok();
-        let config = Config::default();
-        let json = serde_json::to_string_pretty(&config)
-            .map_err(|_| "E18 Failed to serialize config".to_owned())?;
-
-        time!(
-            "Writing config",
-            false,
-            fs::write(&config_file, json).map_err(|_| "E19 Failed to write config file".to_owned())?;
-        );
+    let mut layers = Vec::new();
+    let file_layers = [
+        get_system_config_path().map(ConfigOrigin::System),
+        Some(ConfigOrigin::User(user_config_file)),
+        find_project_config().map(ConfigOrigin::Project),
+    ];
+    for origin in file_layers.into_iter().flatten() {
+        if let Some(path) = origin.path()
+            && path.is_file()
+        {
+            let (layer, unknown) = read_layer(path, &origin)?;
+            warn_unknown_keys(&origin, &unknown);
+            layers.push((origin, layer));
+        }
+    }
+    for (origin, layer) in read_env_layers(&keys) {
+        let unknown = check_layer(Value::Object(layer.clone()))
+            .map_err(|e| layer_error("E236", &origin, e))?;
+        warn_unknown_keys(&origin, &unknown);
+        layers.push((origin, layer));
+    }
+    if let Some(path) = CONFIG_OVERRIDE.get() {
+        if !path.is_file() {
+            return Err(format!(
+                "E235 Config file '{}' not found",
+                path.to_string_lossy()
+            ));
+        }
+        let origin = ConfigOrigin::Override(path.clone());
+        let (layer, unknown) = read_layer(path, &origin)?;
+        warn_unknown_keys(&origin, &unknown);
+        layers.push((origin, layer));
+    }
 
-        config
-    });
-    if !cancel_time {
-        let time = start.elapsed();
-        if *VERBOSE.get().unwrap() {
-            println_task_duration!(time)
+    let mut origins = keys
+        .iter()
+        .map(|k| (k.clone(), ConfigOrigin::Default))
+        .collect::<BTreeMap<_, _>>();
+    for (origin, layer) in layers {
+        for (key, value) in layer {
+            if let Some(current) = values.get_mut(&key) {
+                *current = value;
+                origins.insert(key, origin.clone());
+            }
         }
     }
-    r
+
+    let mut config: Config = serde_json::from_value(Value::Object(values.clone()))
+        .map_err(|e| format!("E236 Invalid config value: {e}"))?;
+    config.origins = origins;
+
+    let time = start.elapsed();
+    if *VERBOSE.get().unwrap() {
+        println_task_duration!(time)
+    }
+    Ok(config)
 }
 
 pub fn reset_config() -> Result<(String, String), String> {
@@ -123,10 +458,10 @@ pub fn edit_config(config: &Config) -> Result<(), String> {
     };
     println!("Opening editor (and waiting for close)...");
 
-    if let Err(e) = config
-        .config_edit_command()
-        .run_command(Some(config_path))?
-    {
+    if let Err(e) = config.config_edit_command().run_command(&EditContext {
+        path: config_path,
+        rss: None,
+    })? {
         return Err(format!(
             "E48 Error when running config edit command: {}\n\
     Check/edit the command used in '{}'.",
@@ -137,3 +472,236 @@ pub fn edit_config(config: &Config) -> Result<(), String> {
 
     Ok(())
 }
+
+/// Prints each config value and the layer that set it
+pub fn show_config_origins(config: &Config) -> Result<(), String> {
+    let values = config_values(config)?;
+
+    if *JSON_OUTPUT.get().unwrap() {
+        let origins = values
+            .into_iter()
+            .map(|(key, value)| {
+                let origin = config.origins().get(&key);
+                (key, json!({ "value": value, "origin": origin }))
+            })
+            .collect::<Map<_, _>>();
+        return print_json(&Value::Object(origins));
+    }
+
+    for (key, value) in values {
+        let origin = config
+            .origins()
+            .get(&key)
+            .cloned()
+            .unwrap_or(ConfigOrigin::Default);
+        cprintln!("<green, bold>{}</> = {} <cyan>({})</>", key, value, origin);
+    }
+    Ok(())
+}
+
+/// Finds the value at a dotted key, e.g. `rust_project_edit_command_blocking.command`
+fn value_at<'a>(values: &'a Map<String, Value>, key: &str) -> Option<&'a Value> {
+    let mut parts = key.split('.');
+    let mut value = values.get(parts.next()?)?;
+    for part in parts {
+        value = value.as_object()?.get(part)?;
+    }
+    Some(value)
+}
+
+fn config_values(config: &Config) -> Result<Map<String, Value>, String> {
+    match serde_json::to_value(config) {
+        Ok(Value::Object(values)) => Ok(values),
+        _ => Err("E13 Failed to serialise config".to_owned()),
+    }
+}
+
+/// The layer that set the value at a dotted key
+fn origin_of<'a>(config: &'a Config, key: &str) -> Option<&'a ConfigOrigin> {
+    config.origins().get(key.split('.').next()?)
+}
+
+/// Prints the value at a dotted key
+pub fn config_get(config: &Config, key: &str) -> Result<(), String> {
+    let values = config_values(config)?;
+    let value = value_at(&values, key).ok_or(format!(
+        "E237 Config key '{key}' not found - see all values with `rss config --show-origin`"
+    ))?;
+
+    if *JSON_OUTPUT.get().unwrap() {
+        return print_json(&json!({
+            "key": key,
+            "value": value,
+            "origin": origin_of(config, key),
+        }));
+    }
+
+    match value {
+        Value::String(s) => println!("{s}"),
+        Value::Object(_) | Value::Array(_) => {
+            let json = serde_json::to_string_pretty(value)
+                .map_err(|_| "E13 Failed to serialise config".to_owned())?;
+            println!("{}", json.to_colored_json_auto().unwrap_or(json));
+        }
+        _ => println!("{value}"),
+    }
+    Ok(())
+}
+
+/// Tells the user if a value written to `layer` is overridden by a higher layer
+pub fn warn_overridden(config: &Config, key: &str, layer: &ConfigOrigin) {
+    if let Some(origin) = origin_of(config, key)
+        && origin > layer
+    {
+        cprintln!(
+            "<yellow, bold>'{}' is currently overridden by {}</>",
+            key,
+            origin
+        );
+    }
+}
+
+/// Sets the value at a dotted key in the config file, returning the layer it was written to
+pub fn set_layer_value(key: &str, value: Value) -> Result<ConfigOrigin, String> {
+    let layer = writable_layer()?;
+    let mut values = read_writable_layer(&layer)?;
+
+    let (parents, last) = match key.rsplit_once('.') {
+        Some((parents, last)) => (parents.split('.').collect::<Vec<_>>(), last),
+        None => (Vec::new(), key),
+    };
+    let mut target = &mut values;
+    for (i, part) in parents.iter().enumerate() {
+        target = target
+            .entry(part.to_string())
+            .or_insert_with(|| Value::Object(Map::new()))
+            .as_object_mut()
+            .ok_or(format!(
+                "E238 '{}' is not an object in {layer}",
+                parents[..=i].join(".")
+            ))?;
+    }
+    target.insert(last.to_owned(), value);
+
+    let unknown =
+        check_layer(Value::Object(values.clone())).map_err(|e| layer_error("E239", &layer, e))?;
+    if unknown
+        .iter()
+        .any(|u| key == u || key.starts_with(&format!("{u}.")))
+    {
+        return Err(format!(
+            "E240 Unknown config key '{key}' - see all values with `rss config --show-origin`"
+        ));
+    }
+    warn_unknown_keys(&layer, &unknown);
+
+    write_layer(layer.path().unwrap(), &values)?;
+    Ok(layer)
+}
+
+/// Removes the value at a dotted key from the config file, returning the layer and whether the
+/// key was set in it
+pub fn unset_layer_value(key: &str) -> Result<(ConfigOrigin, bool), String> {
+    let layer = writable_layer()?;
+    let mut values = read_writable_layer(&layer)?;
+
+    let removed = match key.rsplit_once('.') {
+        Some((parents, last)) => {
+            let mut target = Some(&mut values);
+            for part in parents.split('.') {
+                target = target
+                    .and_then(|t| t.get_mut(part))
+                    .and_then(|v| v.as_object_mut());
+            }
+            target.and_then(|t| t.remove(last))
+        }
+        None => values.remove(key),
+    };
+    if removed.is_none() {
+        return Ok((layer, false));
+    }
+
+    write_layer(layer.path().unwrap(), &values)?;
+    Ok((layer, true))
+}
+
+/// Sets the value at a dotted key in the config file. `value` is JSON, or a plain string.
+pub fn config_set(config: &Config, key: &str, value: &str) -> Result<(), String> {
+    let value = serde_json::from_str(value).unwrap_or(Value::String(value.to_owned()));
+    let layer = set_layer_value(key, value.clone())?;
+    cprintln!("Set <green, bold>{}</> = {} in {}", key, value, layer);
+    warn_overridden(config, key, &layer);
+    Ok(())
+}
+
+/// Removes the value at a dotted key from the config file, so lower layers (or the default) apply
+pub fn config_unset(config: &Config, key: &str) -> Result<(), String> {
+    let (layer, removed) = unset_layer_value(key)?;
+    if !removed {
+        println!("'{key}' is not set in {layer}");
+        return Ok(());
+    }
+    cprintln!("Unset <green, bold>{}</> in {}", key, layer);
+    warn_overridden(config, key, &layer);
+    Ok(())
+}
+
+/// Prints a JSON Schema for config files, for autocompletion in editors
+pub fn config_schema() -> Result<(), String> {
+    let schema = serde_json::to_value(schema_for!(Config))
+        .map_err(|e| format!("E241 Failed to serialise config schema: {e}"))?;
+    print_json(&schema)
+}
+
+/// Prints the editor profiles, and which command `rss edit` will use
+pub fn list_editors(config: &Config) -> Result<(), String> {
+    let profile = config.editor_profile();
+
+    if *JSON_OUTPUT.get().unwrap() {
+        return print_json(&json!({
+            "editors": config.editors(),
+            "default_editor": config.default_editor(),
+            "ssh_editor": config.ssh_editor(),
+            "ssh_session": in_ssh_session(),
+            "selected": profile.as_ref().map(|(name, source)| json!({ "name": name, "source": source })),
+        }));
+    }
+
+    let describe =
+        |command: &EditCommand| format!("{} {}", command.command(), command.args().join(" "));
+
+    if config.editors().is_empty() {
+        println!("No editor profiles - add them to \"editors\" in the config");
+    } else {
+        println!("Editor profiles:");
+    }
+    for (name, command) in config.editors() {
+        let mut roles = Vec::new();
+        if config.default_editor().as_ref() == Some(name) {
+            roles.push("default");
+        }
+        if config.ssh_editor().as_ref() == Some(name) {
+            roles.push("ssh");
+        }
+        let roles = if roles.is_empty() {
+            String::new()
+        } else {
+            format!(" [{}]", roles.join(", "))
+        };
+        cprintln!(
+            "  - <green, bold>{}</>: {}<cyan>{}</>",
+            name,
+            describe(command),
+            roles
+        );
+    }
+
+    match profile {
+        Some((name, source)) => cprintln!("Using <green, bold>{}</> (from {})", name, source),
+        None => cprintln!(
+            "Using <green, bold>rust_project_edit_command_blocking</>: {}",
+            describe(config.rust_project_edit_command_blocking())
+        ),
+    }
+    Ok(())
+}
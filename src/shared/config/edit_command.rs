@@ -1,52 +1,161 @@
 use derive_getters::Getters;
 use either::{Either, Right};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use std::process::{Command, ExitStatus, Output};
 
-const PATH_REPLACE_ARG: &str = "$dir$";
+pub const PATH_REPLACE_ARG: &str = "$dir$";
+const MAIN_PLACEHOLDER: &str = "$main$";
+const MANIFEST_PLACEHOLDER: &str = "$manifest$";
+const NAME_PLACEHOLDER: &str = "$name$";
+const RSS_PLACEHOLDER: &str = "$rss$";
+const CWD_PLACEHOLDER: &str = "$cwd$";
 
-#[derive(Debug, Serialize, Deserialize, Getters)]
+/// Placeholders for the path being edited, one of which must be in the arguments
+const PATH_PLACEHOLDERS: [&str; 3] = [PATH_REPLACE_ARG, MAIN_PLACEHOLDER, MANIFEST_PLACEHOLDER];
+
+#[derive(Debug, Clone, Serialize, Deserialize, Getters, JsonSchema)]
 #[serde(default)]
 pub struct EditCommand {
+    /// Program to run
     command: String,
+    /// Arguments to the program - `$dir$` is replaced with the path being edited. Projects can
+    /// also use `$main$`, `$manifest$`, `$name$` (the rss file's name), `$rss$` (the rss file) and
+    /// `$cwd$`.
     args: Vec<String>,
+    /// Run the program in the current terminal (for terminal editors)
     inherit_shell: bool,
+    /// Extra environment variables for the program (placeholders are replaced in values)
+    env: BTreeMap<String, String>,
+    /// Directory to run the program in (placeholders are replaced)
+    working_dir: Option<String>,
 }
 
-impl EditCommand {
-    pub fn to_command(&self, insert: Option<&str>) -> Result<Command, String> {
-        let mut o_command = Command::new(&self.command);
-        let mut command = &mut o_command;
+/// What an edit command is opening, used to replace placeholders
+pub struct EditContext<'a> {
+    /// The project directory, or the file being edited
+    pub path: &'a str,
+    /// The rss file the project is from, if editing a project
+    pub rss: Option<&'a Path>,
+}
 
-        let mut inserted = false;
-        for arg in &self.args {
-            if arg == PATH_REPLACE_ARG {
-                if let Some(insert) = insert {
-                    command.arg(insert);
-                    inserted = true;
-                } else {
-                    return Err(format!(
-                        "E01 Found `{PATH_REPLACE_ARG}` in command with no insertion"
-                    ));
-                }
-            } else {
-                command = command.arg(arg);
-            }
+impl EditContext<'_> {
+    /// The rss file, made absolute as the editor may run in another directory
+    fn rss_path(&self) -> Option<PathBuf> {
+        self.rss
+            .map(|r| std::path::absolute(r).unwrap_or(r.to_path_buf()))
+    }
+
+    /// Placeholders and their values, which are `None` if they aren't available
+    fn placeholders(&self, cwd: &Path) -> [(&'static str, Option<String>); 6] {
+        let dir = Path::new(self.path);
+        let in_project = |path: PathBuf| self.rss.map(|_| path.to_string_lossy().to_string());
+        [
+            (PATH_REPLACE_ARG, Some(self.path.to_owned())),
+            (
+                MAIN_PLACEHOLDER,
+                in_project(dir.join("src").join("main.rs")),
+            ),
+            (MANIFEST_PLACEHOLDER, in_project(dir.join("Cargo.toml"))),
+            (
+                NAME_PLACEHOLDER,
+                self.rss
+                    .and_then(|r| r.file_stem())
+                    .map(|n| n.to_string_lossy().to_string()),
+            ),
+            (
+                RSS_PLACEHOLDER,
+                self.rss_path().map(|r| r.to_string_lossy().to_string()),
+            ),
+            (CWD_PLACEHOLDER, Some(cwd.to_string_lossy().to_string())),
+        ]
+    }
+}
+
+fn replace_placeholders(
+    value: &str,
+    placeholders: &[(&'static str, Option<String>)],
+) -> Result<String, String> {
+    let mut value = value.to_owned();
+    for (placeholder, replacement) in placeholders {
+        if !value.contains(placeholder) {
+            continue;
+        }
+        let Some(replacement) = replacement else {
+            return Err(format!(
+                "E01 Found `{placeholder}` in command, which can only be used when editing a project"
+            ));
+        };
+        value = value.replace(placeholder, replacement);
+    }
+    Ok(value)
+}
+
+impl EditCommand {
+    pub fn new(command: String, args: Vec<String>, inherit_shell: bool) -> Self {
+        EditCommand {
+            command,
+            args,
+            inherit_shell,
+            ..Default::default()
         }
+    }
+
+    pub fn with_env(mut self, key: &str, value: &str) -> Self {
+        self.env.insert(key.to_owned(), value.to_owned());
+        self
+    }
+
+    pub fn with_working_dir(mut self, working_dir: &str) -> Self {
+        self.working_dir = Some(working_dir.to_owned());
+        self
+    }
 
-        if insert.is_some() && !inserted {
+    pub fn to_command(&self, context: &EditContext) -> Result<Command, String> {
+        // Programs that open their working directory don't need the path as an argument
+        if !self
+            .args
+            .iter()
+            .chain(&self.working_dir)
+            .any(|a| PATH_PLACEHOLDERS.iter().any(|p| a.contains(p)))
+        {
             return Err(format!(
-                "E04 One argument must be `{PATH_REPLACE_ARG}` for directory argument insertion. Check your config file."
+                "E04 One argument (or `working_dir`) must contain `{PATH_REPLACE_ARG}` (or `{MAIN_PLACEHOLDER}`/`{MANIFEST_PLACEHOLDER}` for projects) for path insertion. Check your config file."
             ));
         }
-        Ok(o_command)
+
+        let cwd = std::env::current_dir()
+            .map_err(|e| format!("E242 Failed to get current directory: {e}"))?;
+        let placeholders = context.placeholders(&cwd);
+        let mut command = Command::new(&self.command);
+        for arg in &self.args {
+            command.arg(replace_placeholders(arg, &placeholders)?);
+        }
+        for (key, value) in &self.env {
+            command.env(key, replace_placeholders(value, &placeholders)?);
+        }
+        if let Some(working_dir) = &self.working_dir {
+            command.current_dir(replace_placeholders(working_dir, &placeholders)?);
+        }
+
+        // Lets editor plugins find the rss file a project is from
+        if let Some(rss) = context.rss_path() {
+            command
+                .env("RSS_FILE", rss)
+                .env("RSS_ORIGIN_DIR", &cwd)
+                .env("RSS_PROJECT_DIR", context.path);
+        }
+
+        Ok(command)
     }
 
     pub fn run_command(
         &self,
-        insert: Option<&str>,
+        context: &EditContext,
     ) -> Result<Result<Either<ExitStatus, Output>, std::io::Error>, String> {
-        let mut command = self.to_command(insert)?;
+        let mut command = self.to_command(context)?;
         if self.inherit_shell {
             let result = command.status();
             Ok(result.map(Either::Left))
@@ -68,6 +177,8 @@ impl Default for EditCommand {
                 PATH_REPLACE_ARG.to_owned(),
             ],
             inherit_shell: false,
+            env: BTreeMap::new(),
+            working_dir: None,
         };
         #[cfg(windows)]
         return EditCommand {
@@ -78,6 +189,8 @@ impl Default for EditCommand {
                 PATH_REPLACE_ARG.to_owned(),
             ],
             inherit_shell: false,
+            env: BTreeMap::new(),
+            working_dir: None,
         };
     }
 }
@@ -0,0 +1,221 @@
+use crate::shared::config::edit_command::{EditCommand, PATH_REPLACE_ARG};
+use color_print::cprintln;
+use std::io::{Write, stdin, stdout};
+use std::path::Path;
+
+/// An editor rss knows how to open projects with
+struct KnownEditor {
+    name: &'static str,
+    /// Executables the editor may be installed as
+    executables: &'static [&'static str],
+    /// Arguments before the path, making the editor wait until it is closed
+    args: &'static [&'static str],
+    /// Whether the editor runs in the terminal
+    terminal: bool,
+}
+
+/// Probed for in PATH on first run, in order of preference
+const KNOWN_EDITORS: &[KnownEditor] = &[
+    KnownEditor {
+        name: "VS Code",
+        executables: &["code"],
+        args: &["--new-window", "--wait"],
+        terminal: false,
+    },
+    KnownEditor {
+        name: "VSCodium",
+        executables: &["codium"],
+        args: &["--new-window", "--wait"],
+        terminal: false,
+    },
+    KnownEditor {
+        name: "RustRover",
+        executables: &["rust-rover", "rustrover"],
+        args: &["--wait"],
+        terminal: false,
+    },
+    KnownEditor {
+        name: "Zed",
+        executables: &["zed", "zeditor"],
+        args: &["--new", "--wait"],
+        terminal: false,
+    },
+    KnownEditor {
+        name: "Neovim",
+        executables: &["nvim"],
+        args: &[],
+        terminal: true,
+    },
+    KnownEditor {
+        name: "Vim",
+        executables: &["vim"],
+        args: &[],
+        terminal: true,
+    },
+    KnownEditor {
+        name: "Helix",
+        executables: &["hx", "helix"],
+        args: &[],
+        terminal: true,
+    },
+    KnownEditor {
+        name: "Emacs",
+        executables: &["emacs"],
+        args: &["-nw"],
+        terminal: true,
+    },
+];
+
+/// An editor found on this system
+pub struct DetectedEditor {
+    pub description: String,
+    pub command: EditCommand,
+}
+
+/// Finds an executable in PATH, returning the name to run it with
+fn find_in_path(executable: &str) -> Option<String> {
+    let path = std::env::var_os("PATH")?;
+    #[cfg(unix)]
+    let names = [executable.to_owned()];
+    // VS Code and similar editors are installed with a `.cmd` launcher on Windows
+    #[cfg(windows)]
+    let names = [format!("{executable}.exe"), format!("{executable}.cmd")];
+
+    std::env::split_paths(&path)
+        .find_map(|dir| names.iter().find(|name| dir.join(name).is_file()).cloned())
+}
+
+fn known_editor(executable: &str) -> Option<&'static KnownEditor> {
+    let stem = Path::new(executable).file_stem()?.to_string_lossy();
+    KNOWN_EDITORS
+        .iter()
+        .find(|e| e.executables.contains(&stem.as_ref()))
+}
+
+/// The editor in an environment variable such as `$EDITOR`, which may include arguments
+fn editor_from_env(var: &str) -> Option<DetectedEditor> {
+    let value = std::env::var(var).ok()?;
+    let mut parts = value.split_whitespace();
+    let executable = parts.next()?;
+    let mut args = parts.map(|a| a.to_owned()).collect::<Vec<_>>();
+
+    // Editors in `$VISUAL`/`$EDITOR` are expected to block, and are usually terminal editors
+    let (name, terminal) = match known_editor(executable) {
+        Some(known) => {
+            if args.is_empty() {
+                args = known.args.iter().map(|a| a.to_string()).collect();
+            }
+            (known.name, known.terminal)
+        }
+        None => (executable, true),
+    };
+    args.push(PATH_REPLACE_ARG.to_owned());
+
+    Some(DetectedEditor {
+        description: format!("{name} (${var})"),
+        command: EditCommand::new(executable.to_owned(), args, terminal),
+    })
+}
+
+/// Editors from `$VISUAL`/`$EDITOR` followed by known editors found in PATH
+pub fn detect_editors() -> Vec<DetectedEditor> {
+    let mut editors = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(editor_from_env)
+        .collect::<Vec<_>>();
+
+    for known in KNOWN_EDITORS {
+        let Some(executable) = known.executables.iter().find_map(|e| find_in_path(e)) else {
+            continue;
+        };
+        if editors
+            .iter()
+            .any(|e| known_editor(e.command.command()).is_some_and(|k| k.name == known.name))
+        {
+            continue;
+        }
+        let mut args = known.args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        args.push(PATH_REPLACE_ARG.to_owned());
+        editors.push(DetectedEditor {
+            description: known.name.to_owned(),
+            command: EditCommand::new(executable, args, known.terminal),
+        });
+    }
+
+    editors
+}
+
+/// Asks which of the detected editors to use, or returns `None` if none were found
+pub fn choose_editor() -> Option<EditCommand> {
+    let mut editors = detect_editors();
+    if editors.is_empty() {
+        return None;
+    }
+
+    println!("Choose an editor for rss projects:");
+    for (i, editor) in editors.iter().enumerate() {
+        cprintln!(
+            "  <bold>{}</>) <green, bold>{}</> <cyan>({} {})</>",
+            i + 1,
+            editor.description,
+            editor.command.command(),
+            editor.command.args().join(" ")
+        );
+    }
+
+    loop {
+        print!("Enter a number [1]: ");
+        stdout().flush().ok();
+        let mut input = String::new();
+        // No input (e.g. stdin isn't a terminal) picks the first editor
+        let choice = match stdin().read_line(&mut input) {
+            Ok(0) | Err(_) => 1,
+            Ok(_) if input.trim().is_empty() => 1,
+            Ok(_) => match input.trim().parse::<usize>() {
+                Ok(choice) if (1..=editors.len()).contains(&choice) => choice,
+                _ => {
+                    cprintln!("<red, bold>Enter a number from 1 to {}</>", editors.len());
+                    continue;
+                }
+            },
+        };
+        return Some(editors.swap_remove(choice - 1).command);
+    }
+}
+
+/// The command for `rss edit --quick` - `$EDITOR`, or the first terminal editor found in PATH -
+/// opening `file` within the project
+pub fn quick_editor(file: &str) -> Result<EditCommand, String> {
+    let editor = editor_from_env("EDITOR")
+        .map(|e| e.command)
+        .or_else(|| {
+            KNOWN_EDITORS
+                .iter()
+                .filter(|known| known.terminal)
+                .find_map(|known| {
+                    let executable = known.executables.iter().find_map(|e| find_in_path(e))?;
+                    let mut args = known.args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+                    args.push(PATH_REPLACE_ARG.to_owned());
+                    Some(EditCommand::new(executable, args, true))
+                })
+        })
+        .ok_or("E244 No terminal editor found - set `$EDITOR` to use `--quick`")?;
+
+    let file_arg = format!("{PATH_REPLACE_ARG}/{}", file.trim_start_matches("./"));
+    let args = editor
+        .args()
+        .iter()
+        .map(|a| {
+            if a == PATH_REPLACE_ARG {
+                file_arg.clone()
+            } else {
+                a.clone()
+            }
+        })
+        .collect();
+    Ok(EditCommand::new(
+        editor.command().clone(),
+        args,
+        editor.inherit_shell(),
+    ))
+}
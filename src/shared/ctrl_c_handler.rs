@@ -1,10 +1,12 @@
 use once_cell::sync::OnceCell;
 use std::sync::Mutex;
 
+type CtrlCCallback = Box<dyn FnMut() + Send>;
+
 // Global, not thread-local, with Send requirement on the callback
-static CTRLC_CALLBACK: OnceCell<Mutex<Option<Box<dyn FnMut() + Send>>>> = OnceCell::new();
+static CTRLC_CALLBACK: OnceCell<Mutex<Option<CtrlCCallback>>> = OnceCell::new();
 
-pub fn set_ctrl_c_handler(callback: Box<dyn FnMut() + Send>) {
+pub fn set_ctrl_c_handler(callback: CtrlCCallback) {
     let cell = CTRLC_CALLBACK.get_or_init(|| Mutex::new(None));
     let mut cb = cell.lock().unwrap();
     *cb = Some(callback);
@@ -0,0 +1,179 @@
+use crate::shared::config::Config;
+use crate::shared::util::auto_append_rss;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::read_zip_entries;
+use color_print::cprintln;
+use similar::{ChangeTag, TextDiff};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// A project loaded from either an rss file or a directory
+struct DiffSide {
+    label: String,
+    files: BTreeMap<String, Vec<u8>>,
+    file_contents: Option<FileContents>,
+}
+
+fn load_side<P: AsRef<Path>>(config: &Config, path: P) -> Result<DiffSide, String> {
+    let label = path.as_ref().to_string_lossy().to_string();
+
+    if path.as_ref().is_dir() {
+        return Ok(DiffSide {
+            label,
+            files: read_dir_files(path)?,
+            file_contents: None,
+        });
+    }
+
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E121 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let files = read_zip_entries(path_contents.zipped_contents())?
+        .into_iter()
+        .filter(|e| !e.directory)
+        .map(|e| (e.name, e.contents))
+        .collect();
+
+    Ok(DiffSide {
+        label,
+        files,
+        file_contents: Some(path_contents),
+    })
+}
+
+/// Reads all files in a project directory, skipping the `target` directory as `pack` does
+pub fn read_dir_files<P: AsRef<Path>>(dir: P) -> Result<BTreeMap<String, Vec<u8>>, String> {
+    let mut files = BTreeMap::new();
+    let walker = WalkDir::new(&dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() != 1 || e.file_name() != "target");
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("E122 Failed to read directory: {e}"))?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry
+            .path()
+            .strip_prefix(dir.as_ref())
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let contents =
+            fs::read(entry.path()).map_err(|e| format!("E123 Failed to read file: {e}"))?;
+        files.insert(name, contents);
+    }
+    Ok(files)
+}
+
+pub fn as_text(contents: &[u8]) -> Option<&str> {
+    if contents.contains(&0) {
+        return None;
+    }
+    std::str::from_utf8(contents).ok()
+}
+
+pub fn diff<P: AsRef<Path>, Q: AsRef<Path>>(config: &Config, a: P, b: Q) -> Result<(), String> {
+    let a = load_side(config, a)?;
+    let b = load_side(config, b)?;
+
+    let names = a
+        .files
+        .keys()
+        .chain(b.files.keys())
+        .collect::<BTreeSet<_>>();
+
+    let mut added = Vec::new();
+    let mut removed = Vec::new();
+    let mut changed = 0;
+
+    for name in names {
+        let (a_contents, b_contents) = match (a.files.get(name), b.files.get(name)) {
+            (Some(a_contents), Some(b_contents)) => (a_contents, b_contents),
+            (Some(_), None) => {
+                removed.push(name);
+                continue;
+            }
+            (None, Some(_)) => {
+                added.push(name);
+                continue;
+            }
+            (None, None) => unreachable!(),
+        };
+
+        if a_contents == b_contents {
+            continue;
+        }
+        changed += 1;
+
+        let (Some(a_text), Some(b_text)) = (as_text(a_contents), as_text(b_contents)) else {
+            cprintln!("<bold>Binary file {} differs</>\n", name);
+            continue;
+        };
+
+        print_unified_diff(
+            a_text,
+            b_text,
+            &format!("{}/{name}", a.label),
+            &format!("{}/{name}", b.label),
+        );
+    }
+
+    for name in &removed {
+        cprintln!("<red, bold>Only in {}:</> {}", a.label, name);
+    }
+    for name in &added {
+        cprintln!("<green, bold>Only in {}:</> {}", b.label, name);
+    }
+
+    if let (Some(a_contents), Some(b_contents)) = (&a.file_contents, &b.file_contents) {
+        if a_contents.target_triple() != b_contents.target_triple() {
+            cprintln!(
+                "<yellow, bold>Target triples differ:</> {} ({}) / {} ({})",
+                a_contents.target_triple(),
+                a.label,
+                b_contents.target_triple(),
+                b.label
+            );
+        }
+        if a_contents.bin_contents() != b_contents.bin_contents() {
+            cprintln!("<yellow, bold>Embedded binaries differ</>");
+        }
+    }
+
+    if changed == 0 && added.is_empty() && removed.is_empty() {
+        cprintln!("<green, bold>Projects are identical</>");
+    }
+
+    Ok(())
+}
+
+pub fn print_unified_diff(a: &str, b: &str, a_label: &str, b_label: &str) {
+    let diff = TextDiff::from_lines(a, b);
+    cprintln!("<bold>--- {}</>", a_label);
+    cprintln!("<bold>+++ {}</>", b_label);
+    for hunk in diff.unified_diff().iter_hunks() {
+        cprintln!("<cyan>{}</>", hunk.header());
+        for change in hunk.iter_changes() {
+            let line = change.to_string_lossy();
+            let line = line.trim_end_matches(['\r', '\n']);
+            match change.tag() {
+                ChangeTag::Delete => cprintln!("<red>-{}</>", line),
+                ChangeTag::Insert => cprintln!("<green>+{}</>", line),
+                ChangeTag::Equal => println!(" {line}"),
+            }
+        }
+    }
+    println!();
+}
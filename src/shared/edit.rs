@@ -1,70 +1,64 @@
 use crate::shared::TARGET_TRIPLE;
 use crate::shared::config::Config;
+use crate::shared::import::script_to_project;
+use crate::shared::template::apply_template;
 use crate::shared::util::auto_append_rss;
 use crate::shared::util::edit_recompile_shared::{
-    EditLoopMode, create_temp_project_dir, extract_project, project_edit_loop,
+    EditLoopMode, create_temp_project_dir, extract_project, open_editor, project_edit_loop,
 };
 use crate::shared::util::executable::make_executable;
 use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::record_history;
+use crate::shared::util::lock::RssLock;
 use crate::shared::util::zip::zip_dir_to_bytes;
 use crate::time;
 use color_print::{cformat, cprintln};
+use std::io::{Write, stdin, stdout};
 use std::path::{self, Path, PathBuf};
 use std::{env, fs};
 
-pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), String> {
-    let creating = !path.as_ref().is_file();
-    if !creating && new {
-        return Err("Rss file already exists".to_string());
-    }
-
-    let path = if creating {
-        auto_append_rss(path, config)
-    } else {
-        PathBuf::from(path.as_ref())
-    };
-
-    let path_contents = FileContents::from_path(&path)?;
-
-    let (temp_dir, temp_dir_string, file_name) = create_temp_project_dir(&path)?;
+/// What to create the project from when creating a new rss file
+pub enum NewProjectSource<'a> {
+    Default,
+    Template(&'a str),
+    Script(&'a Path),
+}
 
-    let cargo_path = temp_dir.path().join("Cargo.toml");
+/// How the project is opened for editing
+pub enum EditMode<'a> {
+    /// The project edit command
+    Editor,
+    /// `rss edit --quick` - only this file, in a terminal editor
+    Quick(&'a str),
+    /// `rss shell` - the edit command is a subshell, after which saving can be skipped
+    Shell,
+}
 
-    if let Some(path_contents) = path_contents {
-        extract_project(&path_contents, &temp_dir)?;
-    } else {
-        time!(
-            "Creating default project",
-            true,
-                fs::write(
-            &cargo_path,
-            format!(
-                "[package]\n\
-                name = \"{file_name}\"\n\
-                version = \"0.1.0\"\n\
-                edition = \"2024\"\n\
-                \n\
-                [dependencies]\n"
-            ),
-        )
-        .map_err(|e| format!("E09 Failed to create file: {}", e))?;
-        fs::create_dir(temp_dir.path().join("src"))
-            .map_err(|e| format!("E10 Failed to create directory: {}", e))?;
-        fs::write(
-            temp_dir.path().join("src").join("main.rs"),
-            include_str!("static/main.txt"),
-        )
-        .map_err(|e| format!("E11 Failed to create file: {}", e))?;
-        );
-    }
+pub fn default_manifest(name: &str) -> String {
+    format!(
+        "[package]\n\
+        name = \"{name}\"\n\
+        version = \"0.1.0\"\n\
+        edition = \"2024\"\n\
+        \n\
+        [dependencies]\n"
+    )
+}
 
+/// Creates a script in the project that runs it from the current directory, returning its path
+/// if it should be deleted before saving
+fn create_cr_origin(
+    config: &Config,
+    temp_dir: &Path,
+    cargo_path: &Path,
+) -> Result<Option<PathBuf>, String> {
     let cwd = env::current_dir()
         .map_err(|e| format!("E51 Failed to get current working directory: {}", e))?;
     let cr_origin;
     let delete_cr_origin;
     #[cfg(unix)]
     {
-        cr_origin = temp_dir.path().join("cr-origin.sh");
+        cr_origin = temp_dir.join("cr-origin.sh");
         delete_cr_origin = if !cr_origin.is_file() {
             let escaped_path = cwd.to_string_lossy().replace('\'', "'\\''");
             let escaped_temp_cargo_path = cargo_path.to_string_lossy().replace('\'', "'\\''");
@@ -97,7 +91,7 @@ pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), S
     }
     #[cfg(windows)]
     {
-        cr_origin = temp_dir.path().join("cr-origin.cmd");
+        cr_origin = temp_dir.join("cr-origin.cmd");
         delete_cr_origin = if !cr_origin.is_file() {
             let escaped_path = cwd.to_string_lossy().replace('"', "\"\"");
             let escaped_temp_cargo_path = cargo_path.to_string_lossy().replace('"', "\"\"");
@@ -129,8 +123,107 @@ pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), S
         };
     }
 
+    Ok(delete_cr_origin.then_some(cr_origin))
+}
+
+pub fn edit<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    new: bool,
+    source: NewProjectSource,
+    mode: EditMode,
+) -> Result<(), String> {
+    let creating = !path.as_ref().is_file();
+    if !creating && new {
+        return Err("Rss file already exists".to_string());
+    }
+    if creating && matches!(mode, EditMode::Shell) {
+        return Err(format!(
+            "E246 '{}' not found - create it with `rss new` first",
+            path.as_ref().to_string_lossy()
+        ));
+    }
+
+    let path = if creating {
+        auto_append_rss(path, config)
+    } else {
+        PathBuf::from(path.as_ref())
+    };
+
+    let lock = RssLock::acquire(&path)?;
+    let path_contents = FileContents::from_path(&path)?;
+
+    let (temp_dir, temp_dir_string, file_name) = create_temp_project_dir(&path)?;
+
+    let cargo_path = temp_dir.path().join("Cargo.toml");
+
+    if let Some(path_contents) = &path_contents {
+        extract_project(path_contents, &temp_dir)?;
+    } else if let NewProjectSource::Template(template) = source {
+        time!(
+            format!("Creating project from template '{template}'"),
+            true,
+            apply_template(config, template, &temp_dir, &file_name)?;
+        );
+    } else if let NewProjectSource::Script(script) = source {
+        time!(
+            format!("Importing '{}'", script.to_string_lossy()),
+            true,
+            script_to_project(script, &temp_dir, &file_name)?;
+        );
+    } else {
+        time!(
+            "Creating default project",
+            true,
+                fs::write(
+            &cargo_path,
+            default_manifest(&file_name),
+        )
+        .map_err(|e| format!("E09 Failed to create file: {}", e))?;
+        fs::create_dir(temp_dir.path().join("src"))
+            .map_err(|e| format!("E10 Failed to create directory: {}", e))?;
+        fs::write(
+            temp_dir.path().join("src").join("main.rs"),
+            include_str!("static/main.txt"),
+        )
+        .map_err(|e| format!("E11 Failed to create file: {}", e))?;
+        );
+    }
+
+    if let EditMode::Quick(quick_file) = mode
+        && !temp_dir.path().join(quick_file).is_file()
+    {
+        return Err(format!(
+            "E245 '{quick_file}' not found in the project - create it with `rss put`"
+        ));
+    }
+
+    // Only one file is opened in quick mode, so the script isn't needed
+    let cr_origin = if let EditMode::Quick(_) = mode {
+        None
+    } else {
+        create_cr_origin(config, temp_dir.path(), &cargo_path)?
+    };
+
+    if let EditMode::Shell = mode {
+        cprintln!(
+            "Starting a shell in the project - <bold>`exit`</> to build and save (<cyan>{}</>)",
+            temp_dir_string
+        );
+        open_editor(config, &temp_dir_string, &path)?;
+
+        print!("Save changes to '{}'? (Y/n): ", path.to_string_lossy());
+        stdout().flush().ok();
+        let mut input = String::new();
+        stdin().read_line(&mut input).ok();
+        if input.trim().eq_ignore_ascii_case("n") {
+            cprintln!("<red, bold>Discarded - changes have not been saved</>");
+            return Ok(());
+        }
+    }
+
     let binary = project_edit_loop(
-        false,
+        matches!(mode, EditMode::Shell),
         if config.never_save_binary() {
             EditLoopMode::EditOnly
         } else {
@@ -140,6 +233,7 @@ pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), S
         &temp_dir,
         &temp_dir_string,
         &file_name,
+        &path,
     )?;
 
     let target_dir = temp_dir.path().join("target");
@@ -152,11 +246,11 @@ pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), S
         );
     }
 
-    if delete_cr_origin {
+    if let Some(cr_origin) = &cr_origin {
         time!(
             "Deleting cr-origin",
             false,
-            fs::remove_file(&cr_origin).map_err(|e| format!("E51 Failed to delete file: {}", e))?;
+            fs::remove_file(cr_origin).map_err(|e| format!("E51 Failed to delete file: {}", e))?;
         );
     }
 
@@ -171,7 +265,13 @@ pub fn edit<P: AsRef<Path>>(config: &Config, path: P, new: bool) -> Result<(), S
         cformat!("Writing rss file <red, bold>(no binary)</>... ")
     };
 
-    let file_contents = FileContents::new(project_zip, binary.unwrap_or(vec![]), TARGET_TRIPLE);
+    let mut file_contents = FileContents::new(project_zip, binary.as_ref(), TARGET_TRIPLE)?;
+    record_history(config, path_contents.as_ref(), &mut file_contents, None)?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - changes have not been saved</>");
+        return Ok(());
+    };
 
     time!(
         write_description,
@@ -0,0 +1,197 @@
+use crate::shared::TARGET_TRIPLE;
+use crate::shared::args::ExportFormat;
+use crate::shared::config::Config;
+use crate::shared::diff::as_text;
+use crate::shared::util::auto_append_rss;
+use crate::shared::util::executable::make_executable;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::{ZipEntry, read_zip_entries, zip_entries_to_bytes};
+use crate::time;
+use color_print::cprintln;
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const CARGO_SCRIPT_SHEBANG: &str = "#!/usr/bin/env -S cargo +nightly -Zscript";
+
+/// Builds a `.tar.gz` of the project with all files inside a `name` directory
+fn project_tar_gz(entries: &[ZipEntry], name: &str) -> Result<Vec<u8>, String> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+
+    for entry in entries {
+        let path = format!("{name}/{}", entry.name);
+        let mut header = tar::Header::new_gnu();
+        if entry.directory {
+            header.set_entry_type(tar::EntryType::Directory);
+            header.set_mode(entry.unix_mode.unwrap_or(0o755) & 0o7777);
+            header.set_size(0);
+        } else {
+            header.set_mode(entry.unix_mode.unwrap_or(0o644) & 0o7777);
+            header.set_size(entry.contents.len() as u64);
+        }
+        builder
+            .append_data(&mut header, &path, entry.contents.as_slice())
+            .map_err(|e| format!("E160 Failed to write archive: {e}"))?;
+    }
+
+    builder
+        .into_inner()
+        .and_then(|e| e.finish())
+        .map_err(|e| format!("E160 Failed to write archive: {e}"))
+}
+
+/// Flattens a project consisting of only a `Cargo.toml` and `src/main.rs` into a cargo script
+fn project_cargo_script(entries: &[ZipEntry]) -> Result<String, String> {
+    let mut manifest = None;
+    let mut main = None;
+    let mut others = Vec::new();
+    for entry in entries.iter().filter(|e| !e.directory) {
+        match entry.name.as_str() {
+            "Cargo.toml" => manifest = Some(entry),
+            "src/main.rs" => main = Some(entry),
+            // Regenerated by cargo
+            "Cargo.lock" => {}
+            _ => others.push(entry.name.as_str()),
+        }
+    }
+
+    if !others.is_empty() {
+        return Err(format!(
+            "E161 Only projects containing just `Cargo.toml` and `src/main.rs` can be exported \
+            as a cargo script - also found: {}",
+            others.join(", ")
+        ));
+    }
+    let (Some(manifest), Some(main)) = (manifest, main) else {
+        return Err(
+            "E162 Project must contain `Cargo.toml` and `src/main.rs` to be exported as a cargo \
+            script"
+                .to_owned(),
+        );
+    };
+    let (Some(manifest), Some(main)) = (as_text(&manifest.contents), as_text(&main.contents))
+    else {
+        return Err("E163 `Cargo.toml` and `src/main.rs` must be text files".to_owned());
+    };
+
+    if manifest.lines().any(|l| l.trim_start().starts_with("---")) {
+        return Err("E164 `Cargo.toml` contains a line that would end the frontmatter".to_owned());
+    }
+
+    let mut script = format!("{CARGO_SCRIPT_SHEBANG}\n---cargo\n{manifest}");
+    if !manifest.ends_with('\n') {
+        script.push('\n');
+    }
+    script.push_str("---\n\n");
+    // A shebang in main.rs would no longer be on the first line
+    match main.strip_prefix("#!") {
+        Some(rest) if !rest.starts_with('[') => {
+            script.push_str(rest.split_once('\n').map_or("", |(_, r)| r))
+        }
+        _ => script.push_str(main),
+    }
+    Ok(script)
+}
+
+pub fn export<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    format: ExportFormat,
+    output: Option<&str>,
+    target: Option<&str>,
+    force: bool,
+) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E165 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let name = path
+        .file_stem()
+        .ok_or("E166 Error parsing file name")?
+        .to_string_lossy()
+        .to_string();
+
+    if target.is_some() && format != ExportFormat::Bin {
+        return Err("E167 `--target` can only be used when exporting a binary".to_owned());
+    }
+
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => {
+            let file_name = match format {
+                ExportFormat::Bin if target.unwrap_or(TARGET_TRIPLE).contains("windows") => {
+                    format!("{name}.exe")
+                }
+                ExportFormat::Bin => name.clone(),
+                ExportFormat::TarGz => format!("{name}.tar.gz"),
+                ExportFormat::Zip => format!("{name}.zip"),
+                ExportFormat::CargoScript => format!("{name}.rs"),
+            };
+            path.parent()
+                .ok_or("E168 Error parsing path")?
+                .join(file_name)
+        }
+    };
+
+    if output.exists() && !force {
+        return Err(format!(
+            "E169 '{}' already exists - use `--force` to overwrite it",
+            output.to_string_lossy()
+        ));
+    }
+
+    let bytes = match format {
+        ExportFormat::Bin => {
+            let target = target.unwrap_or(TARGET_TRIPLE);
+            if path_contents.bin_contents().is_empty() {
+                return Err(format!(
+                    "E170 rss file has no binary - build one with `rss recompile {}`",
+                    path.to_string_lossy()
+                ));
+            }
+            if path_contents.target_triple() != target {
+                return Err(format!(
+                    "E171 rss file contains a binary for '{}', not '{target}'",
+                    path_contents.target_triple()
+                ));
+            }
+            path_contents.bin_contents().to_vec()
+        }
+        ExportFormat::TarGz => {
+            project_tar_gz(&read_zip_entries(path_contents.zipped_contents())?, &name)?
+        }
+        ExportFormat::Zip => {
+            let mut entries = read_zip_entries(path_contents.zipped_contents())?;
+            for entry in &mut entries {
+                entry.name = format!("{name}/{}", entry.name);
+            }
+            zip_entries_to_bytes(&entries)?
+        }
+        ExportFormat::CargoScript => {
+            project_cargo_script(&read_zip_entries(path_contents.zipped_contents())?)?.into_bytes()
+        }
+    };
+
+    time!(
+        format!("Writing '{}'", output.to_string_lossy()),
+        false,
+        fs::write(&output, bytes)
+            .map_err(|e| format!("E172 Failed to write '{}': {e}", output.to_string_lossy()))?;
+    );
+
+    if matches!(format, ExportFormat::Bin | ExportFormat::CargoScript) {
+        make_executable(&output)?;
+    }
+
+    cprintln!("Exported to <green, bold>'{}'</>", output.to_string_lossy());
+
+    Ok(())
+}
@@ -1,12 +1,88 @@
 use crate::shared::config::Config;
+use crate::shared::sync::write_link;
 use crate::shared::util::auto_append_rss;
-use crate::shared::util::edit_recompile_shared::extract_project;
+use crate::shared::util::executable::make_executable;
 use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::{Filter, ZipEntry, read_zip_entries};
 use crate::time;
+use color_print::cprintln;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn extract<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
+/// What happened to the files written when extracting
+#[derive(Default)]
+struct ExtractSummary {
+    created: usize,
+    overwritten: usize,
+    unchanged: usize,
+}
+
+fn write_entries(entries: &[ZipEntry], dir: &Path) -> Result<ExtractSummary, String> {
+    let mut summary = ExtractSummary::default();
+
+    for entry in entries {
+        let out_path = dir.join(&entry.name);
+        if entry.directory {
+            fs::create_dir_all(&out_path)
+                .map_err(|e| format!("E30 Failed to create directory: {}", e))?;
+            continue;
+        }
+
+        if let Some(p) = out_path.parent()
+            && !p.exists()
+        {
+            fs::create_dir_all(p).map_err(|e| format!("E31 Failed to create directory: {}", e))?;
+        }
+
+        match fs::read(&out_path) {
+            Ok(existing) if existing == entry.contents => {
+                summary.unchanged += 1;
+                continue;
+            }
+            Ok(_) => summary.overwritten += 1,
+            Err(_) => summary.created += 1,
+        }
+        fs::write(&out_path, &entry.contents)
+            .map_err(|e| format!("E32 Failed to create file: {}", e))?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            if let Some(mode) = entry.unix_mode {
+                fs::set_permissions(&out_path, fs::Permissions::from_mode(mode))
+                    .map_err(|e| format!("E34 Failed to set permissions of file: {}", e))?;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Where the embedded binary is written with `--with-binary` - next to the project directory and
+/// named after the platform it was built for
+fn binary_path(dir: &Path, triple: &str) -> Result<PathBuf, String> {
+    let dir = std::path::absolute(dir)
+        .map_err(|e| format!("E207 Error parsing output directory: {e}"))?;
+    let dir_name = dir
+        .file_name()
+        .ok_or("E207 Error parsing output directory name")?
+        .to_string_lossy();
+    let mut file_name = format!("{dir_name}-{triple}");
+    if triple.contains("windows") {
+        file_name.push_str(".exe");
+    }
+    Ok(dir.with_file_name(file_name))
+}
+
+pub fn extract<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    output: Option<&str>,
+    force: bool,
+    only: &[String],
+    with_binary: bool,
+    link: bool,
+) -> Result<(), String> {
     let path = if path.as_ref().is_file() {
         PathBuf::from(path.as_ref())
     } else {
@@ -18,28 +94,118 @@ pub fn extract<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
         path.as_path().to_string_lossy()
     ))?;
 
-    let dir = path
-        .as_path()
-        .parent()
-        .ok_or("E66 Error parsing path".to_owned())?
-        .join(
-            path.as_path()
-                .file_stem()
-                .ok_or("E67 Error parsing file name".to_owned())?,
+    let dir = match output {
+        Some(output) => PathBuf::from(output),
+        None => path
+            .as_path()
+            .parent()
+            .ok_or("E66 Error parsing path".to_owned())?
+            .join(
+                path.as_path()
+                    .file_stem()
+                    .ok_or("E67 Error parsing file name".to_owned())?,
+            ),
+    };
+
+    let binary_path = if with_binary {
+        if path_contents.bin_contents().is_empty() {
+            return Err(format!(
+                "E208 rss file has no binary - build one with `rss recompile {}`",
+                path.to_string_lossy()
+            ));
+        }
+        let binary_path = binary_path(&dir, &path_contents.target_triple())?;
+        if binary_path.exists() && !force {
+            return Err(format!(
+                "E209 '{}' already exists - use `--force` to overwrite it",
+                binary_path.to_string_lossy()
+            ));
+        }
+        Some(binary_path)
+    } else {
+        None
+    };
+
+    let mut entries = read_zip_entries(path_contents.zipped_contents())?;
+    if !only.is_empty() {
+        let filter = Filter::from_globs(only)?;
+        // Parent directories are created as needed for the matching files
+        entries.retain(|e| !e.directory && filter.matches(Path::new(&e.name)));
+        if entries.is_empty() {
+            return Err(format!("E210 No files match {}", only.join(", ")));
+        }
+    }
+
+    if force {
+        time!(
+            "Creating directory",
+            false,
+            fs::create_dir_all(&dir).map_err(|e| {
+                format!(
+                    "E65 Could not create directory '{}' - {e}",
+                    dir.to_string_lossy()
+                )
+            })?;
         );
+    } else {
+        time!(
+            "Creating directory",
+            false,
+            fs::create_dir(&dir).map_err(|e| {
+                if dir.exists() {
+                    format!(
+                        "E65 Directory '{}' already exists - use `--force` to extract into it",
+                        dir.to_string_lossy()
+                    )
+                } else {
+                    format!(
+                        "E65 Could not create directory '{}' - {e}",
+                        dir.to_string_lossy()
+                    )
+                }
+            })?;
+        );
+    }
+
+    let summary = time!("Extracting project", false, write_entries(&entries, &dir)?);
 
-    time!(
-        "Creating directory",
-        false,
-        fs::create_dir(&dir).map_err(|e| {
-        format!(
-            "E65 Could not create directory '{}' - {e}",
+    if link {
+        write_link(&dir, &path, &path_contents)?;
+    }
+
+    if force {
+        cprintln!(
+            "Extracted to <green, bold>'{}'</> ({} created, {} overwritten, {} unchanged)",
+            dir.to_string_lossy(),
+            summary.created,
+            summary.overwritten,
+            summary.unchanged
+        );
+    } else {
+        cprintln!(
+            "Extracted {} files to <green, bold>'{}'</>",
+            summary.created,
             dir.to_string_lossy()
-        )
-    })?;
-    );
+        );
+    }
 
-    extract_project(&path_contents, &dir)?;
+    if let Some(binary_path) = binary_path {
+        time!(
+            "Writing binary",
+            false,
+            fs::write(&binary_path, path_contents.bin_contents()).map_err(|e| {
+                format!(
+                    "E211 Failed to write '{}': {e}",
+                    binary_path.to_string_lossy()
+                )
+            })?;
+        );
+        make_executable(&binary_path)?;
+        cprintln!(
+            "Wrote binary to <green, bold>'{}'</>",
+            binary_path.to_string_lossy()
+        );
+    }
 
     Ok(())
 }
@@ -0,0 +1,460 @@
+use crate::shared::config::Config;
+use crate::shared::diff::as_text;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::{ZipEntry, read_zip_entries, zip_entries_to_bytes};
+use color_print::cprintln;
+use directories::BaseDirs;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const GIT_ATTRIBUTES_LINE: &str = "*.rss diff=rss merge=rss";
+
+fn contents(entry: Option<&ZipEntry>) -> Option<&Vec<u8>> {
+    entry.map(|e| &e.contents)
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Prints a stable, plain text rendering of an rss file for use as a git `textconv`
+pub fn textconv<P: AsRef<Path>>(path: P) -> Result<(), String> {
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E124 File contents not found: '{}'",
+        path.as_ref().to_string_lossy()
+    ))?;
+
+    println!("target: {}", path_contents.target_triple());
+    if path_contents.bin_contents().is_empty() {
+        println!("binary: none");
+    } else {
+        println!(
+            "binary: {} bytes, sha256 {}",
+            path_contents.bin_contents().len(),
+            sha256(path_contents.bin_contents())
+        );
+    }
+    for (name, section) in path_contents.sections() {
+        println!(
+            "section {name}: {} bytes, sha256 {}",
+            section.len(),
+            sha256(section)
+        );
+    }
+
+    let mut entries = read_zip_entries(path_contents.zipped_contents())?;
+    entries.retain(|e| !e.directory);
+    entries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    for entry in entries {
+        println!();
+        match entry.unix_mode {
+            Some(mode) => println!("=== {} ({:o}) ===", entry.name, mode),
+            None => println!("=== {} ===", entry.name),
+        }
+        match as_text(&entry.contents) {
+            Some(text) => {
+                print!("{text}");
+                if !text.is_empty() && !text.ends_with('\n') {
+                    println!();
+                }
+            }
+            None => println!(
+                "<binary: {} bytes, sha256 {}>",
+                entry.contents.len(),
+                sha256(&entry.contents)
+            ),
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads an rss file given to the merge driver - git passes an empty file when there is no
+/// common ancestor
+fn read_merge_side<P: AsRef<Path>>(
+    path: P,
+) -> Result<(Option<FileContents>, BTreeMap<String, ZipEntry>), String> {
+    let is_empty = fs::metadata(&path)
+        .map_err(|e| format!("E125 Failed to read file: {e}"))?
+        .len()
+        == 0;
+    if is_empty {
+        return Ok((None, BTreeMap::new()));
+    }
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E126 File contents not found: '{}'",
+        path.as_ref().to_string_lossy()
+    ))?;
+    let entries = read_zip_entries(path_contents.zipped_contents())?
+        .into_iter()
+        .filter(|e| !e.directory)
+        .map(|e| (e.name.clone(), e))
+        .collect();
+    Ok((Some(path_contents), entries))
+}
+
+/// Three-way merges the files of two projects with their common ancestor, returning the merged
+/// files and a description of each conflict. Conflicting text files contain conflict markers.
+fn merge_files(
+    ancestor_files: &BTreeMap<String, ZipEntry>,
+    current_files: &BTreeMap<String, ZipEntry>,
+    other_files: &BTreeMap<String, ZipEntry>,
+) -> (Vec<ZipEntry>, Vec<String>) {
+    let names = ancestor_files
+        .keys()
+        .chain(current_files.keys())
+        .chain(other_files.keys())
+        .cloned()
+        .collect::<BTreeSet<_>>();
+
+    let mut conflicts = Vec::new();
+    let mut merged = Vec::new();
+
+    for name in names {
+        let base = ancestor_files.get(&name);
+        let ours = current_files.get(&name);
+        let theirs = other_files.get(&name);
+
+        let result = if contents(ours) == contents(theirs) {
+            ours
+        } else if contents(base) == contents(ours) {
+            theirs
+        } else if contents(base) == contents(theirs) {
+            ours
+        } else {
+            // Changed on both sides
+            match (ours, theirs) {
+                (Some(ours), Some(theirs)) => {
+                    let base_text = base.map_or(Some(""), |b| as_text(&b.contents));
+                    match (
+                        base_text,
+                        as_text(&ours.contents),
+                        as_text(&theirs.contents),
+                    ) {
+                        (Some(base_text), Some(ours_text), Some(theirs_text)) => {
+                            let text = match diffy::merge(base_text, ours_text, theirs_text) {
+                                Ok(text) => text,
+                                Err(text) => {
+                                    conflicts.push(name.clone());
+                                    text
+                                }
+                            };
+                            merged.push(ZipEntry {
+                                name: name.clone(),
+                                contents: text.into_bytes(),
+                                unix_mode: ours.unix_mode,
+                                directory: false,
+                            });
+                            continue;
+                        }
+                        _ => {
+                            conflicts.push(format!("{name} (binary, keeping current version)"));
+                            Some(ours)
+                        }
+                    }
+                }
+                (Some(ours), None) => {
+                    conflicts.push(format!(
+                        "{name} (deleted in other, keeping current version)"
+                    ));
+                    Some(ours)
+                }
+                (None, Some(theirs)) => {
+                    conflicts.push(format!(
+                        "{name} (deleted in current, keeping other version)"
+                    ));
+                    Some(theirs)
+                }
+                (None, None) => None,
+            }
+        };
+
+        if let Some(result) = result {
+            merged.push(ZipEntry {
+                name: result.name.clone(),
+                contents: result.contents.clone(),
+                unix_mode: result.unix_mode,
+                directory: false,
+            });
+        }
+    }
+
+    (merged, conflicts)
+}
+
+/// Three-way merges the projects in two rss files, writing the result over `current`.
+/// Returns whether the merge was clean.
+pub fn merge_driver<P: AsRef<Path>>(
+    config: &Config,
+    ancestor: P,
+    current: P,
+    other: P,
+) -> Result<bool, String> {
+    let (_, ancestor_files) = read_merge_side(&ancestor)?;
+    let (current_contents, current_files) = read_merge_side(&current)?;
+    let (other_contents, other_files) = read_merge_side(&other)?;
+
+    let (merged, conflicts) = merge_files(&ancestor_files, &current_files, &other_files);
+
+    let merged_files = merged
+        .iter()
+        .map(|e| (&e.name, &e.contents))
+        .collect::<BTreeMap<_, _>>();
+    let matches = |files: &BTreeMap<String, ZipEntry>| {
+        files.len() == merged_files.len()
+            && files
+                .iter()
+                .all(|(name, e)| merged_files.get(name) == Some(&&e.contents))
+    };
+
+    // Keep a binary only if it was built from exactly the merged project
+    let (mut file_contents, keep_binary) = match (current_contents, other_contents) {
+        (Some(current_contents), _) if conflicts.is_empty() && matches(&current_files) => {
+            (current_contents, true)
+        }
+        (_, Some(other_contents)) if conflicts.is_empty() && matches(&other_files) => {
+            (other_contents, true)
+        }
+        (Some(current_contents), _) => (current_contents, false),
+        (None, Some(other_contents)) => (other_contents, false),
+        (None, None) => return Err("E127 Nothing to merge".to_owned()),
+    };
+
+    file_contents.replace_project(zip_entries_to_bytes(&merged)?);
+    if !keep_binary {
+        file_contents.remove_binary();
+    }
+    file_contents.save_without_backup(&current, config)?;
+
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+
+    cprintln!("<red, bold>Conflicts when merging rss file:</>");
+    for conflict in &conflicts {
+        println!("  - {conflict}");
+    }
+    Ok(false)
+}
+
+fn run_git(args: &[&str], dir: &Path) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("E128 Failed to run git: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "E129 `git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+}
+
+/// The global git attributes file - `core.attributesFile`, or git's default if that isn't set
+fn global_attributes_path(cwd: &Path) -> Result<PathBuf, String> {
+    let args = ["config", "--global", "--path", "core.attributesFile"];
+    if let Ok(path) = run_git(&args, cwd)
+        && !path.is_empty()
+    {
+        return Ok(PathBuf::from(path));
+    }
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|d| !d.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => BaseDirs::new()
+            .ok_or("E255 Failed to get home directory")?
+            .home_dir()
+            .join(".config"),
+    };
+    Ok(config_dir.join("git").join("attributes"))
+}
+
+/// Configures git to diff and merge rss files using rss, in the current repository or globally
+pub fn git_setup(global: bool) -> Result<(), String> {
+    let cwd = std::env::current_dir()
+        .map_err(|e| format!("E130 Failed to get current working directory: {e}"))?;
+    // Global setup doesn't need a repository, so it can be run from anywhere
+    let (scope, dir, attributes_path) = if global {
+        ("--global", cwd.clone(), global_attributes_path(&cwd)?)
+    } else {
+        let repo_root = PathBuf::from(run_git(&["rev-parse", "--show-toplevel"], &cwd)?);
+        let attributes_path = repo_root.join(".gitattributes");
+        ("--local", repo_root, attributes_path)
+    };
+
+    for (key, value) in [
+        ("diff.rss.textconv", "rss textconv"),
+        ("diff.rss.cachetextconv", "true"),
+        ("merge.rss.name", "rss project merge driver"),
+        ("merge.rss.driver", "rss merge-driver %O %A %B"),
+    ] {
+        run_git(&["config", scope, key, value], &dir)?;
+        cprintln!("Set git config <cyan>{}</> to <cyan>'{}'</>", key, value);
+    }
+
+    let attributes = if attributes_path.is_file() {
+        fs::read_to_string(&attributes_path)
+            .map_err(|e| format!("E131 Failed to read git attributes: {e}"))?
+    } else {
+        String::new()
+    };
+
+    if attributes.lines().any(|l| l.trim() == GIT_ATTRIBUTES_LINE) {
+        cprintln!(
+            "<cyan>'{}'</> already configured",
+            attributes_path.to_string_lossy()
+        );
+    } else {
+        let mut new_attributes = attributes;
+        if !new_attributes.is_empty() && !new_attributes.ends_with('\n') {
+            new_attributes.push('\n');
+        }
+        new_attributes.push_str(GIT_ATTRIBUTES_LINE);
+        new_attributes.push('\n');
+        if let Some(parent) = attributes_path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("E132 Failed to write git attributes: {e}"))?;
+        }
+        fs::write(&attributes_path, new_attributes)
+            .map_err(|e| format!("E132 Failed to write git attributes: {e}"))?;
+        cprintln!(
+            "Added <cyan>'{}'</> to '{}'",
+            GIT_ATTRIBUTES_LINE,
+            attributes_path.to_string_lossy()
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, contents: &[u8]) -> ZipEntry {
+        ZipEntry {
+            name: name.to_owned(),
+            contents: contents.to_vec(),
+            unix_mode: None,
+            directory: false,
+        }
+    }
+
+    fn files(entries: &[(&str, &str)]) -> BTreeMap<String, ZipEntry> {
+        entries
+            .iter()
+            .map(|(name, text)| (name.to_string(), entry(name, text.as_bytes())))
+            .collect()
+    }
+
+    fn merged_text(merged: &[ZipEntry], name: &str) -> Option<String> {
+        merged
+            .iter()
+            .find(|e| e.name == name)
+            .map(|e| String::from_utf8(e.contents.clone()).unwrap())
+    }
+
+    #[test]
+    fn takes_changes_from_either_side() {
+        let base = files(&[("a.rs", "a\n"), ("b.rs", "b\n"), ("c.rs", "c\n")]);
+        let ours = files(&[("a.rs", "a2\n"), ("b.rs", "b\n"), ("c.rs", "c\n")]);
+        let theirs = files(&[("a.rs", "a\n"), ("b.rs", "b2\n"), ("c.rs", "c\n")]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged_text(&merged, "a.rs").as_deref(), Some("a2\n"));
+        assert_eq!(merged_text(&merged, "b.rs").as_deref(), Some("b2\n"));
+        assert_eq!(merged_text(&merged, "c.rs").as_deref(), Some("c\n"));
+    }
+
+    #[test]
+    fn adds_and_deletes_files() {
+        let base = files(&[("old.rs", "old\n"), ("kept.rs", "kept\n")]);
+        let ours = files(&[("kept.rs", "kept\n")]);
+        let theirs = files(&[
+            ("old.rs", "old\n"),
+            ("kept.rs", "kept\n"),
+            ("new.rs", "new\n"),
+        ]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(merged_text(&merged, "old.rs"), None);
+        assert_eq!(merged_text(&merged, "new.rs").as_deref(), Some("new\n"));
+        assert_eq!(merged_text(&merged, "kept.rs").as_deref(), Some("kept\n"));
+    }
+
+    #[test]
+    fn merges_separate_changes_to_the_same_file() {
+        let base = files(&[("main.rs", "one\ntwo\nthree\nfour\nfive\n")]);
+        let ours = files(&[("main.rs", "ONE\ntwo\nthree\nfour\nfive\n")]);
+        let theirs = files(&[("main.rs", "one\ntwo\nthree\nfour\nFIVE\n")]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert!(conflicts.is_empty());
+        assert_eq!(
+            merged_text(&merged, "main.rs").as_deref(),
+            Some("ONE\ntwo\nthree\nfour\nFIVE\n")
+        );
+    }
+
+    #[test]
+    fn writes_conflict_markers() {
+        let base = files(&[("main.rs", "fn main() {}\n")]);
+        let ours = files(&[("main.rs", "fn main() { a(); }\n")]);
+        let theirs = files(&[("main.rs", "fn main() { b(); }\n")]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert_eq!(conflicts, vec!["main.rs".to_owned()]);
+        let text = merged_text(&merged, "main.rs").unwrap();
+        assert!(text.contains("<<<<<<<"));
+        assert!(text.contains("fn main() { a(); }"));
+        assert!(text.contains("fn main() { b(); }"));
+        assert!(text.contains(">>>>>>>"));
+    }
+
+    #[test]
+    fn merges_files_added_on_both_sides_without_an_ancestor() {
+        let base = BTreeMap::new();
+        let ours = files(&[("main.rs", "a\n")]);
+        let theirs = files(&[("main.rs", "b\n")]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert_eq!(conflicts, vec!["main.rs".to_owned()]);
+        assert!(merged_text(&merged, "main.rs").unwrap().contains("<<<<<<<"));
+    }
+
+    #[test]
+    fn keeps_current_binary_file_on_conflict() {
+        let mut base = BTreeMap::new();
+        base.insert("icon.png".to_owned(), entry("icon.png", &[0, 1, 2]));
+        let mut ours = BTreeMap::new();
+        ours.insert("icon.png".to_owned(), entry("icon.png", &[0, 1, 3]));
+        let mut theirs = BTreeMap::new();
+        theirs.insert("icon.png".to_owned(), entry("icon.png", &[0, 1, 4]));
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with("icon.png (binary"));
+        assert_eq!(merged[0].contents, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn keeps_modified_file_deleted_on_the_other_side() {
+        let base = files(&[("lib.rs", "a\n")]);
+        let ours = files(&[("lib.rs", "b\n")]);
+        let theirs = files(&[]);
+
+        let (merged, conflicts) = merge_files(&base, &ours, &theirs);
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].starts_with("lib.rs (deleted in other"));
+        assert_eq!(merged_text(&merged, "lib.rs").as_deref(), Some("b\n"));
+    }
+}
@@ -0,0 +1,164 @@
+use crate::shared::JSON_OUTPUT;
+use crate::shared::config::Config;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::{History, record_history};
+use crate::shared::util::lock::RssLock;
+use crate::shared::util::zip::{CatOptions, Filter, cat_files};
+use crate::shared::util::{auto_append_rss, print_json};
+use crate::time;
+use color_print::{cformat, cprintln};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+pub fn history<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E116 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let history = History::from_file_contents(&path_contents)?;
+
+    if *JSON_OUTPUT.get().unwrap() {
+        let current = history.latest_matches(path_contents.zipped_contents())?;
+        let latest_id = history.revisions().last().map(|r| r.id());
+        return print_json(&Value::Array(
+            history
+                .revisions()
+                .iter()
+                .rev()
+                .map(|revision| {
+                    json!({
+                        "id": revision.id(),
+                        "time": revision.time(),
+                        "files": revision.file_count(),
+                        "message": revision.message(),
+                        "current": current && Some(revision.id()) == latest_id,
+                    })
+                })
+                .collect(),
+        ));
+    }
+
+    if history.revisions().is_empty() {
+        cprintln!("<yellow, bold>No history found</>");
+        if config.history_length() == 0 {
+            cprintln!(
+                "<cyan>Set <blue, bold>\"history_length\"</> in the config to start recording history</>"
+            );
+        }
+        return Ok(());
+    }
+
+    let current = history.latest_matches(path_contents.zipped_contents())?;
+    let latest_id = history.revisions().last().map(|r| r.id());
+
+    for revision in history.revisions().iter().rev() {
+        cprintln!(
+            "<green, bold>{:>4}</>  {}  <cyan>({} files)</>{}  {}",
+            revision.id(),
+            revision.time(),
+            revision.file_count(),
+            if current && Some(revision.id()) == latest_id {
+                cformat!(" <yellow, bold>[current]</>")
+            } else {
+                String::new()
+            },
+            revision.message().unwrap_or("")
+        );
+    }
+
+    Ok(())
+}
+
+pub fn show<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    revision: Option<u64>,
+    filter: Filter,
+    options: CatOptions,
+) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E117 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let history = History::from_file_contents(&path_contents)?;
+    let revision = match revision {
+        Some(revision) => revision,
+        None => history
+            .revisions()
+            .last()
+            .ok_or("E118 No history found".to_owned())?
+            .id(),
+    };
+
+    cat_files(&history.revision_zip(revision)?, filter, options)?;
+
+    Ok(())
+}
+
+pub fn revert<P: AsRef<Path>>(config: &Config, path: P, revision: u64) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let lock = RssLock::acquire(&path)?;
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E119 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let history = History::from_file_contents(&path_contents)?;
+    let project_zip = history.revision_zip(revision)?;
+
+    // The binary is no longer built from the project so is removed - it will be rebuilt on the
+    // next run
+    let mut new_contents = path_contents.clone();
+    new_contents.replace_project(project_zip);
+    new_contents.remove_binary();
+    record_history(
+        config,
+        Some(&path_contents),
+        &mut new_contents,
+        Some(format!("Revert to revision {revision}")),
+    )?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - file has not been reverted</>");
+        return Ok(());
+    };
+
+    time!(
+        format!("Reverting to revision {revision}"),
+        true,
+        new_contents.save(&path, config)?;
+    );
+
+    cprintln!(
+        "<yellow, bold>The compiled binary has been removed and will be rebuilt when next run</>"
+    );
+
+    new_contents.print_stats(
+        &path
+            .as_path()
+            .file_name()
+            .ok_or("E120 Failed to read filename from path")?
+            .to_string_lossy(),
+    );
+
+    Ok(())
+}
@@ -0,0 +1,342 @@
+use crate::shared::TARGET_TRIPLE;
+use crate::shared::config::Config;
+use crate::shared::edit::default_manifest;
+use crate::shared::util::edit_recompile_shared::{
+    EditLoopMode, create_temp_project_dir, project_edit_loop,
+};
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::record_history;
+use crate::shared::util::lock::RssLock;
+use crate::shared::util::zip::zip_dir_to_bytes;
+use crate::time;
+use color_print::{cformat, cprintln};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table, value};
+
+/// A single-file cargo script split into its manifest and source
+pub struct CargoScript {
+    pub manifest: Option<String>,
+    pub body: String,
+}
+
+/// Splits a cargo script into its (optional) `---cargo` frontmatter and Rust source, dropping any
+/// shebang line
+pub fn parse_cargo_script(source: &str) -> Result<CargoScript, String> {
+    let mut rest = source;
+    if rest.starts_with("#!") && !rest.starts_with("#![") {
+        rest = rest.split_once('\n').map_or("", |(_, r)| r);
+    }
+
+    // Frontmatter may only be preceded by blank lines
+    let trimmed = rest.trim_start_matches(['\n', '\r', ' ', '\t']);
+    let dashes = trimmed.len() - trimmed.trim_start_matches('-').len();
+    if dashes < 3 {
+        return Ok(CargoScript {
+            manifest: None,
+            body: rest.to_owned(),
+        });
+    }
+
+    let (opening, after_opening) = trimmed.split_once('\n').unwrap_or((trimmed, ""));
+    let info = opening[dashes..].trim();
+    if !info.is_empty() && info != "cargo" {
+        return Err(format!(
+            "E150 Unsupported frontmatter type '{info}' (expected `cargo`)"
+        ));
+    }
+
+    let fence = &opening[..dashes];
+    let mut manifest = String::new();
+    let mut remaining = after_opening;
+    loop {
+        let Some((line, next)) = remaining
+            .split_once('\n')
+            .or((!remaining.is_empty()).then_some((remaining, "")))
+        else {
+            return Err("E151 Unterminated frontmatter - missing closing `---`".to_owned());
+        };
+        remaining = next;
+        if line.trim_end() == fence {
+            break;
+        }
+        manifest.push_str(line);
+        manifest.push('\n');
+    }
+
+    Ok(CargoScript {
+        manifest: Some(manifest),
+        body: remaining.trim_start_matches(['\n', '\r']).to_owned(),
+    })
+}
+
+/// Turns a cargo script manifest into a full `Cargo.toml`, filling in the package fields that
+/// cargo scripts are allowed to leave out
+fn complete_manifest(manifest: &str, name: &str) -> Result<String, String> {
+    let mut document = manifest
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("E152 Failed to parse frontmatter manifest: {e}"))?;
+
+    if !document.contains_key("package") {
+        // Insert `[package]` before any other tables, separated by a blank line
+        for (_, item) in document.iter_mut() {
+            if let Some(table) = item.as_table_mut()
+                && table
+                    .decor()
+                    .prefix()
+                    .is_none_or(|p| p.as_str() == Some(""))
+            {
+                table.decor_mut().set_prefix("\n");
+            }
+        }
+        let mut package = Table::new();
+        package.set_position(Some(0));
+        document.insert("package", Item::Table(package));
+    }
+    let package = document["package"]
+        .as_table_mut()
+        .ok_or("E153 `package` in frontmatter manifest is not a table")?;
+
+    for (key, default) in [("name", name), ("version", "0.1.0"), ("edition", "2024")] {
+        if !package.contains_key(key) {
+            package.insert(key, value(default));
+        }
+    }
+    package.sort_values_by(|a, _, b, _| {
+        let order = |k: &str| ["name", "version", "edition"].iter().position(|o| *o == k);
+        order(a.get())
+            .unwrap_or(3)
+            .cmp(&order(b.get()).unwrap_or(3))
+    });
+
+    if !document.contains_key("dependencies") {
+        document.insert("dependencies", Item::Table(Table::new()));
+    }
+
+    Ok(document.to_string())
+}
+
+/// Creates a project in `dir` from a cargo script (or plain `.rs` file)
+pub fn script_to_project<P: AsRef<Path>, Q: AsRef<Path>>(
+    script: P,
+    dir: Q,
+    name: &str,
+) -> Result<(), String> {
+    let source = fs::read_to_string(&script).map_err(|e| {
+        format!(
+            "E154 Failed to read '{}': {e}",
+            script.as_ref().to_string_lossy()
+        )
+    })?;
+    let script = parse_cargo_script(&source)?;
+
+    let manifest = match &script.manifest {
+        Some(manifest) => complete_manifest(manifest, name)?,
+        None => default_manifest(name),
+    };
+
+    fs::write(dir.as_ref().join("Cargo.toml"), manifest)
+        .map_err(|e| format!("E155 Failed to create file: {e}"))?;
+    fs::create_dir_all(dir.as_ref().join("src"))
+        .map_err(|e| format!("E156 Failed to create directory: {e}"))?;
+    fs::write(dir.as_ref().join("src").join("main.rs"), script.body)
+        .map_err(|e| format!("E155 Failed to create file: {e}"))?;
+    Ok(())
+}
+
+pub fn import<P: AsRef<Path>>(
+    config: &Config,
+    script: P,
+    output: Option<&str>,
+    force: bool,
+) -> Result<(), String> {
+    let output = match output {
+        Some(output) => PathBuf::from(output),
+        None => PathBuf::from(
+            script
+                .as_ref()
+                .file_name()
+                .ok_or("E157 Failed to read filename from path")?,
+        )
+        .with_extension("rss"),
+    };
+    if output.exists() && !force {
+        return Err(format!(
+            "E250 '{}' already exists - use `--force` to overwrite it",
+            output.to_string_lossy()
+        ));
+    }
+
+    let lock = RssLock::acquire(&output)?;
+    // Kept so the history of a file being overwritten isn't lost
+    let path_contents = FileContents::from_path(&output)?;
+
+    let (temp_dir, temp_dir_string, file_name) = create_temp_project_dir(&output)?;
+
+    time!(
+        "Importing script",
+        false,
+        script_to_project(&script, &temp_dir, &file_name)?;
+    );
+
+    let binary = if config.never_save_binary() {
+        None
+    } else {
+        project_edit_loop(
+            true,
+            EditLoopMode::CompileBinary,
+            config,
+            &temp_dir,
+            &temp_dir_string,
+            &file_name,
+            &output,
+        )?
+    };
+
+    let target_dir = temp_dir.path().join("target");
+    if target_dir.exists() {
+        time!(
+            "Cleaning up target directory",
+            false,
+            fs::remove_dir_all(target_dir)
+            .map_err(|e| format!("E158 Failed to remove target directory: {}", e))?;
+        );
+    }
+
+    let project_zip = time!("Zipping project", false, zip_dir_to_bytes(&temp_dir)?);
+
+    let write_description = if binary.is_some() {
+        cformat!(
+            "Writing rss file <green, bold>(project and binary - {})</>... ",
+            TARGET_TRIPLE
+        )
+    } else {
+        cformat!("Writing rss file <red, bold>(no binary)</>... ")
+    };
+
+    let mut file_contents = FileContents::new(project_zip, binary.as_ref(), TARGET_TRIPLE)?;
+    record_history(config, path_contents.as_ref(), &mut file_contents, None)?;
+
+    let Some(output) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - changes have not been saved</>");
+        return Ok(());
+    };
+
+    time!(
+        write_description,
+        false,
+        file_contents.save(&output, config)?;
+    );
+
+    file_contents.print_stats(&output.to_string_lossy());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn script_without_frontmatter() {
+        let script = parse_cargo_script("fn main() {}\n").unwrap();
+        assert_eq!(script.manifest, None);
+        assert_eq!(script.body, "fn main() {}\n");
+    }
+
+    #[test]
+    fn splits_frontmatter() {
+        let script =
+            parse_cargo_script("---cargo\n[dependencies]\nclap = \"4\"\n---\n\nfn main() {}\n")
+                .unwrap();
+        assert_eq!(
+            script.manifest.as_deref(),
+            Some("[dependencies]\nclap = \"4\"\n")
+        );
+        assert_eq!(script.body, "fn main() {}\n");
+    }
+
+    #[test]
+    fn frontmatter_without_info_string() {
+        let script = parse_cargo_script("---\n[dependencies]\n---\nfn main() {}\n").unwrap();
+        assert_eq!(script.manifest.as_deref(), Some("[dependencies]\n"));
+        assert_eq!(script.body, "fn main() {}\n");
+    }
+
+    #[test]
+    fn drops_shebang() {
+        let script = parse_cargo_script(
+            "#!/usr/bin/env -S cargo +nightly -Zscript\n---cargo\n[dependencies]\n---\nfn main() {}\n",
+        )
+        .unwrap();
+        assert_eq!(script.manifest.as_deref(), Some("[dependencies]\n"));
+        assert_eq!(script.body, "fn main() {}\n");
+
+        let script = parse_cargo_script("#!/usr/bin/env rust-script\nfn main() {}\n").unwrap();
+        assert_eq!(script.manifest, None);
+        assert_eq!(script.body, "fn main() {}\n");
+    }
+
+    #[test]
+    fn keeps_inner_attribute() {
+        let source = "#![allow(unused)]\nfn main() {}\n";
+        let script = parse_cargo_script(source).unwrap();
+        assert_eq!(script.manifest, None);
+        assert_eq!(script.body, source);
+    }
+
+    #[test]
+    fn allows_blank_lines_before_frontmatter() {
+        let script =
+            parse_cargo_script("\n\n---cargo\n[dependencies]\n---\nfn main() {}\n").unwrap();
+        assert_eq!(script.manifest.as_deref(), Some("[dependencies]\n"));
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        let script =
+            parse_cargo_script("---cargo\r\n[dependencies]\r\n---\r\n\r\nfn main() {}\r\n")
+                .unwrap();
+        assert_eq!(script.manifest.as_deref(), Some("[dependencies]\r\n"));
+        assert_eq!(script.body, "fn main() {}\r\n");
+    }
+
+    #[test]
+    fn closing_fence_must_match_opening() {
+        let script = parse_cargo_script(
+            "----cargo\n[package]\n---\nedition = \"2024\"\n----\nfn main() {}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            script.manifest.as_deref(),
+            Some("[package]\n---\nedition = \"2024\"\n")
+        );
+        assert_eq!(script.body, "fn main() {}\n");
+    }
+
+    #[test]
+    fn unterminated_frontmatter() {
+        let error = parse_cargo_script("---cargo\n[dependencies]\nfn main() {}\n")
+            .err()
+            .unwrap();
+        assert!(error.starts_with("E151"));
+    }
+
+    #[test]
+    fn unsupported_frontmatter_type() {
+        let error = parse_cargo_script("---toml\n[dependencies]\n---\nfn main() {}\n")
+            .err()
+            .unwrap();
+        assert!(error.starts_with("E150"));
+    }
+
+    #[test]
+    fn completes_manifest() {
+        let manifest = complete_manifest("[dependencies]\nclap = \"4\"\n", "script").unwrap();
+        assert_eq!(
+            manifest,
+            "[package]\nname = \"script\"\nversion = \"0.1.0\"\nedition = \"2024\"\n\n\
+            [dependencies]\nclap = \"4\"\n"
+        );
+    }
+}
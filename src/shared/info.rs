@@ -0,0 +1,347 @@
+use crate::shared::JSON_OUTPUT;
+use crate::shared::config::Config;
+use crate::shared::diff::as_text;
+use crate::shared::util::binaries::stored_targets;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::read_zip_entries;
+use crate::shared::util::{auto_append_rss, print_json};
+use color_print::cprintln;
+use serde::Serialize;
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, Table};
+
+/// Number of README lines shown before it is cut off (the full README is included with
+/// `--format json`)
+const README_PREVIEW_LINES: usize = 20;
+
+const DEPENDENCY_KINDS: [(&str, &str); 3] = [
+    ("dependencies", "Dependencies"),
+    ("dev-dependencies", "Dev dependencies"),
+    ("build-dependencies", "Build dependencies"),
+];
+
+#[derive(Serialize)]
+struct Dependency {
+    name: String,
+    /// Version requirement, or where the dependency comes from if it isn't from a registry
+    requirement: String,
+    optional: bool,
+    /// Versions in `Cargo.lock`
+    resolved: Vec<String>,
+}
+
+fn string_field(package: Option<&Table>, key: &str) -> Option<String> {
+    package?.get(key)?.as_str().map(|v| v.to_owned())
+}
+
+/// Describes where a dependency comes from, e.g. `^1.0`, `path = "../common"`
+fn requirement(item: &Item) -> String {
+    if let Some(version) = item.as_str() {
+        return version.to_owned();
+    }
+    let Some(table) = item.as_table_like() else {
+        return "*".to_owned();
+    };
+    if let Some(version) = table.get("version").and_then(|v| v.as_str()) {
+        return version.to_owned();
+    }
+    for key in ["path", "git"] {
+        if let Some(source) = table.get(key).and_then(|v| v.as_str()) {
+            return format!("{key} = \"{source}\"");
+        }
+    }
+    if table.get("workspace").and_then(|w| w.as_bool()) == Some(true) {
+        return "workspace".to_owned();
+    }
+    "*".to_owned()
+}
+
+/// Package name -> versions in `Cargo.lock`
+fn locked_versions(lock: Option<&str>) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut versions = BTreeMap::<String, Vec<String>>::new();
+    let Some(lock) = lock else {
+        return Ok(versions);
+    };
+    let document = lock
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("E226 Failed to parse Cargo.lock: {e}"))?;
+    let packages = document
+        .get("package")
+        .and_then(|p| p.as_array_of_tables())
+        .into_iter()
+        .flatten();
+    for package in packages {
+        if let (Some(name), Some(version)) = (
+            package.get("name").and_then(|n| n.as_str()),
+            package.get("version").and_then(|v| v.as_str()),
+        ) {
+            versions
+                .entry(name.to_owned())
+                .or_default()
+                .push(version.to_owned());
+        }
+    }
+    Ok(versions)
+}
+
+fn dependencies(table: Option<&Item>, locked: &BTreeMap<String, Vec<String>>) -> Vec<Dependency> {
+    let Some(table) = table.and_then(|t| t.as_table_like()) else {
+        return Vec::new();
+    };
+    table
+        .iter()
+        .map(|(name, item)| {
+            let dependency = item.as_table_like();
+            // Renamed dependencies are locked under their real package name
+            let package = dependency
+                .and_then(|d| d.get("package"))
+                .and_then(|p| p.as_str())
+                .unwrap_or(name);
+            Dependency {
+                name: name.to_owned(),
+                requirement: requirement(item),
+                optional: dependency
+                    .and_then(|d| d.get("optional"))
+                    .and_then(|o| o.as_bool())
+                    .unwrap_or(false),
+                resolved: locked.get(package).cloned().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
+
+/// Binaries declared with `[[bin]]` and those cargo discovers automatically
+fn declared_binaries(
+    manifest: &DocumentMut,
+    package_name: Option<&str>,
+    files: &BTreeMap<String, Vec<u8>>,
+) -> Vec<String> {
+    let mut binaries = BTreeSet::new();
+    let bins = manifest
+        .get("bin")
+        .and_then(|b| b.as_array_of_tables())
+        .into_iter()
+        .flatten();
+    for bin in bins {
+        if let Some(name) = bin.get("name").and_then(|n| n.as_str()) {
+            binaries.insert(name.to_owned());
+        }
+    }
+
+    let autobins = manifest
+        .get("package")
+        .and_then(|p| p.get("autobins"))
+        .and_then(|a| a.as_bool())
+        .unwrap_or(true);
+    if autobins {
+        if files.contains_key("src/main.rs")
+            && let Some(package_name) = package_name
+        {
+            binaries.insert(package_name.to_owned());
+        }
+        for name in files.keys() {
+            let Some(rest) = name.strip_prefix("src/bin/") else {
+                continue;
+            };
+            let bin = match rest.split_once('/') {
+                Some((dir, "main.rs")) => dir,
+                None => match rest.strip_suffix(".rs") {
+                    Some(bin) => bin,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            binaries.insert(bin.to_owned());
+        }
+    }
+
+    binaries.into_iter().collect()
+}
+
+/// The first block of inner doc comments (`//!`) in `src/main.rs`
+fn main_doc_comment(main: &str) -> Option<String> {
+    let lines = main
+        .lines()
+        .map(|l| l.trim())
+        .skip_while(|l| {
+            l.is_empty() || l.starts_with("#!") || (l.starts_with("//") && !l.starts_with("//!"))
+        })
+        .take_while(|l| l.starts_with("//!"))
+        .map(|l| {
+            let l = &l[3..];
+            l.strip_prefix(' ').unwrap_or(l)
+        })
+        .collect::<Vec<_>>();
+    let doc = lines.join("\n").trim().to_owned();
+    (!doc.is_empty()).then_some(doc)
+}
+
+pub fn info<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E227 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let file_name = path
+        .file_name()
+        .ok_or("E228 Failed to read filename from path")?
+        .to_string_lossy()
+        .to_string();
+
+    let files = read_zip_entries(path_contents.zipped_contents())?
+        .into_iter()
+        .filter(|e| !e.directory)
+        .map(|e| (e.name, e.contents))
+        .collect::<BTreeMap<_, _>>();
+    let text = |name: &str| files.get(name).and_then(|c| as_text(c));
+
+    let manifest = text("Cargo.toml")
+        .ok_or("E229 Project has no Cargo.toml")?
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("E230 Failed to parse Cargo.toml: {e}"))?;
+    let locked = locked_versions(text("Cargo.lock"))?;
+
+    let package = manifest.get("package").and_then(|p| p.as_table());
+    let name = string_field(package, "name");
+    let version = string_field(package, "version");
+    let edition = string_field(package, "edition");
+    let description = string_field(package, "description");
+    let authors = package
+        .and_then(|p| p.get("authors"))
+        .and_then(|a| a.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|a| a.as_str().map(|a| a.to_owned()))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    let binaries = declared_binaries(&manifest, name.as_deref(), &files);
+    let dependencies = DEPENDENCY_KINDS
+        .iter()
+        .map(|(key, _)| (*key, dependencies(manifest.get(key), &locked)))
+        .collect::<Vec<_>>();
+    let features = manifest
+        .get("features")
+        .and_then(|f| f.as_table_like())
+        .map(|f| {
+            f.iter()
+                .map(|(name, enables)| {
+                    let enables = enables
+                        .as_array()
+                        .map(|a| {
+                            a.iter()
+                                .filter_map(|e| e.as_str().map(|e| e.to_owned()))
+                                .collect::<Vec<_>>()
+                        })
+                        .unwrap_or_default();
+                    (name.to_owned(), enables)
+                })
+                .collect::<BTreeMap<_, _>>()
+        })
+        .unwrap_or_default();
+    let readme = string_field(package, "readme")
+        .into_iter()
+        .chain(["README.md", "README", "README.txt"].map(|r| r.to_owned()))
+        .find_map(|r| text(&r).map(|t| (r, t.to_owned())));
+    let doc_comment = text("src/main.rs").and_then(main_doc_comment);
+
+    if *JSON_OUTPUT.get().unwrap() {
+        return print_json(&json!({
+            "file": file_name,
+            "name": name,
+            "version": version,
+            "edition": edition,
+            "description": description,
+            "authors": authors,
+            "binaries": binaries,
+            "built_binaries": stored_targets(&path_contents)?,
+            "dependencies": dependencies.into_iter().collect::<BTreeMap<_, _>>(),
+            "features": features,
+            "readme": readme.map(|(name, contents)| json!({ "file": name, "contents": contents })),
+            "doc_comment": doc_comment,
+        }));
+    }
+
+    cprintln!(
+        "{}: <green, bold>{}</> <cyan>v{}</>{}",
+        file_name,
+        name.as_deref().unwrap_or("(unnamed)"),
+        version.as_deref().unwrap_or("?"),
+        edition
+            .map(|e| format!(" (edition {e})"))
+            .unwrap_or_default()
+    );
+    if let Some(description) = &description {
+        println!("  {description}");
+    }
+    if !authors.is_empty() {
+        println!("  Authors: {}", authors.join(", "));
+    }
+
+    if !binaries.is_empty() {
+        cprintln!("Binaries: <green, bold>{}</>", binaries.join(", "));
+    }
+
+    for ((_, label), (_, dependencies)) in DEPENDENCY_KINDS.iter().zip(&dependencies) {
+        if dependencies.is_empty() {
+            continue;
+        }
+        println!("{label}:");
+        for dependency in dependencies {
+            let resolved = if dependency.resolved.is_empty() {
+                String::new()
+            } else {
+                format!(" -> {}", dependency.resolved.join(", "))
+            };
+            cprintln!(
+                "  - <green, bold>{}</> <cyan>{}</>{}{}",
+                dependency.name,
+                dependency.requirement,
+                resolved,
+                if dependency.optional {
+                    " (optional)"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+
+    if !features.is_empty() {
+        println!("Features:");
+        for (feature, enables) in &features {
+            cprintln!("  - <green, bold>{}</> = [{}]", feature, enables.join(", "));
+        }
+    }
+
+    if let Some((readme_name, readme)) = &readme {
+        cprintln!("\n<bold>{}</>", readme_name);
+        let lines = readme.lines().collect::<Vec<_>>();
+        for line in lines.iter().take(README_PREVIEW_LINES) {
+            println!("  {line}");
+        }
+        if lines.len() > README_PREVIEW_LINES {
+            cprintln!(
+                "  <cyan>... ({} more lines - see <blue, bold>`rss cat {} -n {}`</>)</>",
+                lines.len() - README_PREVIEW_LINES,
+                path.to_string_lossy(),
+                readme_name
+            );
+        }
+    } else if let Some(doc_comment) = &doc_comment {
+        cprintln!("\n<bold>src/main.rs</>");
+        for line in doc_comment.lines() {
+            println!("  {line}");
+        }
+    }
+
+    Ok(())
+}
@@ -27,6 +27,7 @@ pub fn install<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
         &temp_dir,
         &temp_dir_string,
         &file_name,
+        &path,
     )?;
 
     Ok(())
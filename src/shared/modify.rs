@@ -0,0 +1,251 @@
+use crate::shared::TARGET_TRIPLE;
+use crate::shared::config::Config;
+use crate::shared::util::auto_append_rss;
+use crate::shared::util::edit_recompile_shared::{
+    build_binaries, create_temp_project_dir, extract_project,
+};
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::record_history;
+use crate::shared::util::lock::RssLock;
+use crate::shared::util::zip::{ZipEntry, read_zip_entries, zip_entries_to_bytes};
+use crate::time;
+use color_print::cprintln;
+use std::fs;
+use std::io::{Read, stdin};
+use std::path::{Path, PathBuf};
+
+/// Normalises a path within the project (e.g. `./src\main.rs` -> `src/main.rs`)
+fn normalise_inner_path(inner_path: &str) -> Result<String, String> {
+    let normalised = inner_path
+        .replace('\\', "/")
+        .split('/')
+        .filter(|p| !p.is_empty() && *p != ".")
+        .collect::<Vec<_>>()
+        .join("/");
+    if normalised.is_empty() || normalised.split('/').any(|p| p == "..") {
+        return Err(format!("E136 Invalid path within project: '{inner_path}'"));
+    }
+    Ok(normalised)
+}
+
+fn is_within(name: &str, inner_path: &str) -> bool {
+    let name = name.trim_end_matches('/');
+    name == inner_path || name.starts_with(&format!("{inner_path}/"))
+}
+
+/// Adds directory entries for any parent directories of `name` that don't exist
+fn add_parent_dirs(entries: &mut Vec<ZipEntry>, name: &str) {
+    let parts = name.split('/').collect::<Vec<_>>();
+    for i in 1..parts.len() {
+        let dir = parts[..i].join("/");
+        if !entries
+            .iter()
+            .any(|e| e.directory && e.name.trim_end_matches('/') == dir)
+        {
+            entries.push(ZipEntry {
+                name: format!("{dir}/"),
+                contents: Vec::new(),
+                unix_mode: None,
+                directory: true,
+            });
+        }
+    }
+}
+
+/// Applies `modify` to the project within an rss file, then rebuilds or removes the
+/// (now stale) binary
+fn modify_project<P: AsRef<Path>, F: FnOnce(&mut Vec<ZipEntry>) -> Result<(), String>>(
+    config: &Config,
+    path: P,
+    rebuild: bool,
+    message: String,
+    modify: F,
+) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    let lock = RssLock::acquire(&path)?;
+    let path_contents = FileContents::from_path(&path)?.ok_or(format!(
+        "E137 File contents not found: '{}'",
+        path.as_path().to_string_lossy()
+    ))?;
+
+    let mut entries = read_zip_entries(path_contents.zipped_contents())?;
+    modify(&mut entries)?;
+
+    let mut new_contents = path_contents.clone();
+    new_contents.replace_project(time!(
+        "Zipping project",
+        false,
+        zip_entries_to_bytes(&entries)?
+    ));
+
+    let binary = if rebuild && !config.never_save_binary() {
+        let (temp_dir, _, file_name) = create_temp_project_dir(&path)?;
+        extract_project(&new_contents, &temp_dir)?;
+        // Fail rather than prompting, as stdin may be the file contents (or not a terminal)
+        Some(build_binaries(config, temp_dir.path(), &file_name)?)
+    } else {
+        None
+    };
+
+    match binary {
+        Some(binary) => new_contents.replace_binaries(TARGET_TRIPLE, &binary)?,
+        None => {
+            if !path_contents.bin_contents().is_empty() {
+                cprintln!(
+                    "<yellow, bold>Removing the outdated binary - it will be rebuilt when next run</>"
+                );
+            }
+            new_contents.remove_binary();
+        }
+    }
+
+    record_history(
+        config,
+        Some(&path_contents),
+        &mut new_contents,
+        Some(message),
+    )?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - changes have not been saved</>");
+        return Ok(());
+    };
+
+    time!(
+        "Writing rss file",
+        false,
+        new_contents.save(&path, config)?;
+    );
+
+    new_contents.print_stats(
+        &path
+            .as_path()
+            .file_name()
+            .ok_or("E138 Failed to read filename from path")?
+            .to_string_lossy(),
+    );
+
+    Ok(())
+}
+
+pub fn put<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    inner_path: &str,
+    from: Option<&str>,
+    rebuild: bool,
+) -> Result<(), String> {
+    let inner_path = normalise_inner_path(inner_path)?;
+
+    let (contents, unix_mode) = if let Some(from) = from {
+        let contents = fs::read(from).map_err(|e| format!("E139 Failed to read '{from}': {e}"))?;
+        #[cfg(unix)]
+        let unix_mode = {
+            use std::os::unix::fs::PermissionsExt;
+            fs::metadata(from).ok().map(|m| m.permissions().mode())
+        };
+        #[cfg(windows)]
+        let unix_mode = None;
+        (contents, unix_mode)
+    } else {
+        let mut contents = Vec::new();
+        stdin()
+            .read_to_end(&mut contents)
+            .map_err(|e| format!("E140 Failed to read from stdin: {e}"))?;
+        (contents, None)
+    };
+
+    modify_project(
+        config,
+        path,
+        rebuild,
+        format!("rss put {inner_path}"),
+        |entries| {
+            if entries
+                .iter()
+                .any(|e| e.directory && e.name.trim_end_matches('/') == inner_path)
+            {
+                return Err(format!("E141 '{inner_path}' is a directory"));
+            }
+
+            if let Some(existing) = entries.iter_mut().find(|e| e.name == inner_path) {
+                existing.contents = contents;
+                if unix_mode.is_some() {
+                    existing.unix_mode = unix_mode;
+                }
+            } else {
+                add_parent_dirs(entries, &inner_path);
+                entries.push(ZipEntry {
+                    name: inner_path.clone(),
+                    contents,
+                    unix_mode,
+                    directory: false,
+                });
+            }
+            Ok(())
+        },
+    )
+}
+
+pub fn rm<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    inner_path: &str,
+    rebuild: bool,
+) -> Result<(), String> {
+    let inner_path = normalise_inner_path(inner_path)?;
+
+    modify_project(
+        config,
+        path,
+        rebuild,
+        format!("rss rm {inner_path}"),
+        |entries| {
+            let before = entries.len();
+            entries.retain(|e| !is_within(&e.name, &inner_path));
+            if entries.len() == before {
+                return Err(format!("E142 '{inner_path}' not found in project"));
+            }
+            Ok(())
+        },
+    )
+}
+
+pub fn mv<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    from: &str,
+    to: &str,
+    rebuild: bool,
+) -> Result<(), String> {
+    let from = normalise_inner_path(from)?;
+    let to = normalise_inner_path(to)?;
+
+    modify_project(
+        config,
+        path,
+        rebuild,
+        format!("rss mv {from} {to}"),
+        |entries| {
+            if !entries.iter().any(|e| is_within(&e.name, &from)) {
+                return Err(format!("E142 '{from}' not found in project"));
+            }
+            if entries.iter().any(|e| is_within(&e.name, &to)) {
+                return Err(format!("E143 '{to}' already exists in project"));
+            }
+
+            for entry in entries.iter_mut() {
+                if is_within(&entry.name, &from) {
+                    entry.name = format!("{to}{}", &entry.name[from.len()..]);
+                }
+            }
+            add_parent_dirs(entries, &to);
+            Ok(())
+        },
+    )
+}
@@ -1,25 +1,186 @@
 use crate::shared::TARGET_TRIPLE;
 use crate::shared::config::Config;
+use crate::shared::util::auto_append_rss;
 use crate::shared::util::edit_recompile_shared::{EditLoopMode, project_edit_loop};
+use crate::shared::util::embed_deps::embed_path_dependencies;
 use crate::shared::util::file_contents::FileContents;
-use crate::shared::util::zip::zip_dir_to_bytes;
+use crate::shared::util::history::record_history;
+use crate::shared::util::zip::{unzip_from_bytes, zip_dir_to_bytes};
 use crate::time;
 use color_print::cformat;
+use flate2::read::GzDecoder;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use toml_edit::DocumentMut;
 
-pub fn pack<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
-    let dir = path
+/// Where to read the project being packed from
+pub enum PackSource<'a> {
+    /// A project directory or a `.tar.gz`, `.zip` or `.crate` archive
+    Path(&'a str),
+    /// A revision of a local git repository
+    Git {
+        repo: &'a str,
+        rev: &'a str,
+        subdir: Option<&'a str>,
+    },
+}
+
+/// Unpacks a (possibly gzipped) tar archive into `dir`
+fn unpack_tar<R: std::io::Read>(reader: R, dir: &Path) -> Result<(), String> {
+    tar::Archive::new(reader)
+        .unpack(dir)
+        .map_err(|e| format!("E173 Failed to unpack archive: {e}"))
+}
+
+fn unpack_archive(archive: &Path, dir: &Path) -> Result<(), String> {
+    let file_name = archive
+        .file_name()
+        .ok_or("E174 Failed to read filename from path")?
+        .to_string_lossy()
+        .to_ascii_lowercase();
+
+    time!(
+        "Unpacking archive",
+        false,
+        if file_name.ends_with(".zip") {
+            let bytes =
+                fs::read(archive).map_err(|e| format!("E175 Failed to read archive: {e}"))?;
+            unzip_from_bytes(&bytes, dir)?;
+        } else if [".tar.gz", ".tgz", ".crate"]
+            .iter()
+            .any(|e| file_name.ends_with(e))
+        {
+            let file =
+                fs::File::open(archive).map_err(|e| format!("E175 Failed to read archive: {e}"))?;
+            unpack_tar(GzDecoder::new(file), dir)?;
+        } else {
+            return Err(format!(
+                "E176 Unsupported archive '{}' (expected a directory, .tar.gz, .zip or .crate)",
+                archive.to_string_lossy()
+            ));
+        }
+    );
+    Ok(())
+}
+
+fn unpack_git(repo: &str, rev: &str, dir: &Path) -> Result<(), String> {
+    let output = time!(
+        format!("Reading '{rev}' from git"),
+        false,
+        Command::new("git")
+            .args(["-C", repo, "archive", "--format=tar", rev])
+            .output()
+            .map_err(|e| format!("E128 Failed to run git: {e}"))?
+    );
+    if !output.status.success() {
+        return Err(format!(
+            "E177 `git archive {rev}` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    unpack_tar(output.stdout.as_slice(), dir)
+}
+
+/// Archives usually contain the project within a single top level directory (e.g.
+/// `name-version/` in a `.crate`)
+fn find_project_root(dir: &Path) -> Result<PathBuf, String> {
+    if dir.join("Cargo.toml").is_file() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let entries = fs::read_dir(dir)
+        .map_err(|e| format!("E178 Failed to read directory: {e}"))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("E178 Failed to read directory: {e}"))?;
+    if let [entry] = entries.as_slice()
+        && entry.path().join("Cargo.toml").is_file()
+    {
+        return Ok(entry.path());
+    }
+
+    Err("E179 No Cargo.toml found in the project".to_owned())
+}
+
+fn package_name(dir: &Path) -> Result<String, String> {
+    let manifest = fs::read_to_string(dir.join("Cargo.toml"))
+        .map_err(|e| format!("E180 Failed to read Cargo.toml: {e}"))?;
+    let document = manifest
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("E181 Failed to parse Cargo.toml: {e}"))?;
+    document
+        .get("package")
+        .and_then(|p| p.get("name"))
+        .and_then(|n| n.as_str())
+        .map(|n| n.to_owned())
+        .ok_or("E182 Cargo.toml has no package name".to_owned())
+}
+
+pub fn pack(
+    config: &Config,
+    source: PackSource,
+    output: Option<&str>,
+    force: bool,
+) -> Result<(), String> {
+    // Archives and git revisions are unpacked to (and built in) a temporary directory
+    let mut _temp_dir = None;
+    let (dir, dir_name) = match source {
+        PackSource::Path(path) if Path::new(path).is_dir() => {
+            let dir = Path::new(path)
+                .canonicalize()
+                .map_err(|e| format!("E70 Error parsing path: {:?}", e))?;
+            let dir_name = dir
+                .file_name()
+                .ok_or("E72 Failed to directory name")?
+                .to_string_lossy()
+                .to_string();
+            (dir, dir_name)
+        }
+        source => {
+            let temp_dir =
+                TempDir::new().map_err(|e| format!("E05 Failed to create temp directory: {e}"))?;
+            // The whole git tree is unpacked so that workspace and sibling crates are available
+            // when embedding path dependencies
+            let unpacked = match source {
+                PackSource::Path(path) => {
+                    unpack_archive(Path::new(path), temp_dir.path())?;
+                    temp_dir.path().to_path_buf()
+                }
+                PackSource::Git { repo, rev, subdir } => {
+                    unpack_git(repo, rev, temp_dir.path())?;
+                    match subdir {
+                        Some(subdir) => temp_dir.path().join(subdir.trim_matches('/')),
+                        None => temp_dir.path().to_path_buf(),
+                    }
+                }
+            };
+            let dir = find_project_root(&unpacked)?;
+            let dir_name = package_name(&dir)?;
+            _temp_dir = Some(temp_dir);
+            (dir, dir_name)
+        }
+    };
+
+    // Crates outside the project are copied in so the rss file can be built on its own
+    let embedded = embed_path_dependencies(&dir)?;
+    let dir = embedded
         .as_ref()
-        .canonicalize()
-        .map_err(|e| format!("E70 Error parsing path: {:?}", e))?;
+        .map_or(dir, |temp_dir| temp_dir.path().to_path_buf());
 
     let dir_string = dir.to_str().ok_or("E71 Failed to get directory path")?;
 
-    let dir_name = dir
-        .file_name()
-        .ok_or("E72 Failed to directory name")?
-        .to_string_lossy();
+    let file_name = match output {
+        Some(output) => auto_append_rss(output, config),
+        None => PathBuf::from(format!("{dir_name}.rss")),
+    };
+    if file_name.exists() && !force {
+        return Err(format!(
+            "E183 '{}' already exists - use `--force` to overwrite it",
+            file_name.to_string_lossy()
+        ));
+    }
 
     let binary = if config.never_save_binary() {
         None
@@ -31,6 +192,7 @@ pub fn pack<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
             &dir,
             dir_string,
             &dir_name,
+            &file_name,
         )?
     };
 
@@ -55,16 +217,16 @@ pub fn pack<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
         cformat!("Writing rss file <red, bold>(no binary)</>... ")
     };
 
-    let file_contents = FileContents::new(project_zip, binary.unwrap_or(vec![]), TARGET_TRIPLE);
+    let mut file_contents = FileContents::new(project_zip, binary.as_ref(), TARGET_TRIPLE)?;
+    record_history(config, None, &mut file_contents, None)?;
 
-    let file_name = format!("{dir_name}.rss");
     time!(
         write_description,
         false,
         file_contents.save(&file_name, config)?;
     );
 
-    file_contents.print_stats(&file_name);
+    file_contents.print_stats(&file_name.to_string_lossy());
 
     Ok(())
 }
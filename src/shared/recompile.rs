@@ -1,21 +1,24 @@
 use crate::shared::TARGET_TRIPLE;
 use crate::shared::config::Config;
 use crate::shared::util::auto_append_rss;
+use crate::shared::util::binaries::Binaries;
 use crate::shared::util::edit_recompile_shared::{
     EditLoopMode, create_temp_project_dir, extract_project, project_edit_loop,
 };
 use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::lock::RssLock;
 use crate::time;
 use color_print::{cformat, cprintln};
 use std::path::{Path, PathBuf};
 
-pub fn recompile<P: AsRef<Path>>(config: &Config, path: P) -> Result<Option<Vec<u8>>, String> {
+pub fn recompile<P: AsRef<Path>>(config: &Config, path: P) -> Result<Option<Binaries>, String> {
     let path = if path.as_ref().is_file() {
         PathBuf::from(path.as_ref())
     } else {
         auto_append_rss(path, config)
     };
 
+    let lock = RssLock::acquire(&path)?;
     let mut path_contents = FileContents::from_path(&path)?
         .ok_or(format!("E45 File contents not found: {:?}", path.as_path()))?;
 
@@ -30,6 +33,7 @@ pub fn recompile<P: AsRef<Path>>(config: &Config, path: P) -> Result<Option<Vec<
         &temp_dir,
         &temp_dir_string,
         &file_name,
+        &path,
     )?;
 
     let Some(binary) = binary else {
@@ -44,7 +48,13 @@ pub fn recompile<P: AsRef<Path>>(config: &Config, path: P) -> Result<Option<Vec<
         return Ok(Some(binary));
     }
 
-    path_contents.replace_binary(TARGET_TRIPLE, &binary);
+    path_contents.replace_binaries(TARGET_TRIPLE, &binary)?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - compiled binary has not been saved</>");
+        return Ok(Some(binary));
+    };
+
     time!(
         cformat!("Writing binary ({}) to rss file", TARGET_TRIPLE),
         false,
@@ -0,0 +1,111 @@
+use crate::shared::JSON_OUTPUT;
+use crate::shared::config::Config;
+use crate::shared::util::backup::{backup_path, list_backups};
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::lock::RssLock;
+use crate::shared::util::{auto_append_rss, print_json};
+use crate::time;
+use chrono::{DateTime, Local};
+use color_print::cprintln;
+use human_bytes::human_bytes;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub fn restore<P: AsRef<Path>>(
+    config: &Config,
+    path: P,
+    backup: usize,
+    list: bool,
+) -> Result<(), String> {
+    let path = if path.as_ref().is_file() {
+        PathBuf::from(path.as_ref())
+    } else {
+        auto_append_rss(path, config)
+    };
+
+    if list {
+        let backups = list_backups(&path);
+        if *JSON_OUTPUT.get().unwrap() {
+            let mut json = Vec::with_capacity(backups.len());
+            for (n, backup) in backups {
+                let metadata = fs::metadata(&backup)
+                    .map_err(|e| format!("E101 Failed to read backup metadata: {e}"))?;
+                json.push(json!({
+                    "backup": n,
+                    "path": backup,
+                    "size": metadata.len(),
+                    "modified": metadata
+                        .modified()
+                        .ok()
+                        .map(|m| DateTime::<Local>::from(m).to_rfc3339()),
+                }));
+            }
+            return print_json(&Value::Array(json));
+        }
+
+        if backups.is_empty() {
+            cprintln!(
+                "<yellow, bold>No backups found for '{}'</>",
+                path.to_string_lossy()
+            );
+            return Ok(());
+        }
+
+        println!("Backups of '{}':", path.to_string_lossy());
+        for (n, backup) in backups {
+            let metadata = fs::metadata(&backup)
+                .map_err(|e| format!("E101 Failed to read backup metadata: {e}"))?;
+            let modified = metadata
+                .modified()
+                .map(|m| {
+                    DateTime::<Local>::from(m)
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                })
+                .unwrap_or_else(|_| "unknown".to_owned());
+            cprintln!(
+                "  <green, bold>{n}</>: {} [<cyan>{}</> | {}]",
+                backup.to_string_lossy(),
+                human_bytes(metadata.len() as f64),
+                modified
+            );
+        }
+        return Ok(());
+    }
+
+    let backup = backup_path(&path, backup);
+    if !backup.is_file() {
+        return Err(format!(
+            "E102 Backup '{}' not found. Use `rss restore --list` to see available backups.",
+            backup.to_string_lossy()
+        ));
+    }
+
+    let lock = RssLock::acquire(&path)?;
+    let backup_contents = FileContents::from_path(&backup)?.ok_or(format!(
+        "E103 File contents not found: '{}'",
+        backup.to_string_lossy()
+    ))?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - backup has not been restored</>");
+        return Ok(());
+    };
+
+    time!(
+        format!("Restoring '{}'", backup.to_string_lossy()),
+        true,
+        backup_contents.save(&path, config)?;
+    );
+
+    backup_contents.print_stats(
+        &path
+            .as_path()
+            .file_name()
+            .ok_or("E104 Failed to read filename from path")?
+            .to_string_lossy(),
+    );
+
+    Ok(())
+}
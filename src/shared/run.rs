@@ -1,5 +1,8 @@
 use crate::shared::config::Config;
 use crate::shared::util::auto_append_rss;
+use crate::shared::util::binaries::{
+    BinaryTarget, missing_target_error, stored_binary, stored_targets,
+};
 use crate::shared::util::executable::make_executable;
 use crate::shared::util::file_contents::FileContents;
 use crate::shared::{TARGET_TRIPLE, VERBOSE};
@@ -21,10 +24,10 @@ pub enum RunParam<P: AsRef<Path>> {
 pub fn run<P: AsRef<Path>>(
     config: &Config,
     run_param: RunParam<P>,
+    target: Option<&BinaryTarget>,
     args: &[String],
 ) -> Result<Result<i32, String>, String> {
-    let mut _maybe_path_contents = None;
-    let bin = match &run_param {
+    let bin = match run_param {
         RunParam::Path(path) => {
             let path = if path.as_ref().is_file() {
                 PathBuf::from(path.as_ref())
@@ -46,8 +49,22 @@ pub fn run<P: AsRef<Path>>(
                     TARGET_TRIPLE
                 )));
             }
-            _maybe_path_contents = Some(path_contents);
-            _maybe_path_contents.as_ref().unwrap().bin_contents()
+
+            let file_name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            match stored_binary(&path_contents, file_name, target)? {
+                Some(bin) => bin,
+                None => {
+                    let target = target.unwrap();
+                    // The stored binaries are always built from the current project
+                    if let Some(targets) = stored_targets(&path_contents)? {
+                        return Err(missing_target_error(target, targets.iter()));
+                    }
+                    return Ok(Err(format!("rss file has no binary for {target}")));
+                }
+            }
         }
         RunParam::Binary(b) => b,
     };
@@ -0,0 +1,108 @@
+use crate::shared::config::edit_command::{EditCommand, PATH_REPLACE_ARG};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// The user's shell
+fn user_shell() -> PathBuf {
+    #[cfg(unix)]
+    return std::env::var_os("SHELL")
+        .filter(|s| !s.is_empty())
+        .map_or(PathBuf::from("/bin/sh"), PathBuf::from);
+    #[cfg(windows)]
+    return std::env::var_os("COMSPEC")
+        .filter(|s| !s.is_empty())
+        .map_or(PathBuf::from("cmd.exe"), PathBuf::from);
+}
+
+/// Quotes `value` for use in a POSIX shell script
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn write_startup_file(dir: &Path, name: &str, contents: &str) -> Result<PathBuf, String> {
+    let path = dir.join(name);
+    fs::write(&path, contents)
+        .map_err(|e| format!("E247 Failed to write shell startup file: {e}"))?;
+    Ok(path)
+}
+
+/// The command for `rss shell` - the user's shell, started in the project with `(rss <name>)`
+/// added to its prompt. The returned directory holds the shell's startup files and must be kept
+/// until the shell exits.
+pub fn shell_command(name: &str) -> Result<(EditCommand, TempDir), String> {
+    let startup_dir =
+        TempDir::new().map_err(|e| format!("E05 Failed to create temp directory: {e}"))?;
+    let shell = user_shell();
+    let shell_name = shell
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let marker = format!("(rss {name}) ");
+
+    let (args, env) = match shell_name.as_str() {
+        "bash" => {
+            let rc = write_startup_file(
+                startup_dir.path(),
+                "bashrc",
+                &format!(
+                    "[ -f ~/.bashrc ] && . ~/.bashrc\nPS1={}\"$PS1\"\n",
+                    sh_quote(&marker)
+                ),
+            )?;
+            (
+                vec!["--rcfile".to_owned(), rc.to_string_lossy().to_string()],
+                vec![],
+            )
+        }
+        "zsh" => {
+            // zsh reads its startup files from `$ZDOTDIR`, so these load the user's own files
+            let user_dir = std::env::var("ZDOTDIR")
+                .ok()
+                .or_else(|| std::env::var("HOME").ok())
+                .unwrap_or_default();
+            write_startup_file(
+                startup_dir.path(),
+                ".zshenv",
+                "[ -f \"$RSS_ZDOTDIR/.zshenv\" ] && . \"$RSS_ZDOTDIR/.zshenv\"\n",
+            )?;
+            write_startup_file(
+                startup_dir.path(),
+                ".zshrc",
+                &format!(
+                    "ZDOTDIR=\"$RSS_ZDOTDIR\"\n\
+                    [ -f \"$ZDOTDIR/.zshrc\" ] && . \"$ZDOTDIR/.zshrc\"\n\
+                    PROMPT={}\"$PROMPT\"\n",
+                    sh_quote(&marker.replace('%', "%%"))
+                ),
+            )?;
+            (
+                vec![],
+                vec![
+                    ("RSS_ZDOTDIR", user_dir),
+                    ("ZDOTDIR", startup_dir.path().to_string_lossy().to_string()),
+                ],
+            )
+        }
+        "fish" => (
+            vec![
+                "--init-command".to_owned(),
+                format!(
+                    "functions -c fish_prompt _rss_fish_prompt; \
+                    function fish_prompt; echo -n {}; _rss_fish_prompt; end",
+                    sh_quote(&marker)
+                ),
+            ],
+            vec![],
+        ),
+        "cmd" => (vec![], vec![("PROMPT", format!("{marker}$P$G"))]),
+        _ => (vec![], vec![("PS1", format!("{marker}$ "))]),
+    };
+
+    let mut command = EditCommand::new(shell.to_string_lossy().to_string(), args, true)
+        .with_working_dir(PATH_REPLACE_ARG);
+    for (key, value) in env {
+        command = command.with_env(key, &value);
+    }
+    Ok((command, startup_dir))
+}
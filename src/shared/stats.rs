@@ -1,6 +1,9 @@
+use crate::shared::JSON_OUTPUT;
 use crate::shared::config::Config;
-use crate::shared::util::auto_append_rss;
+use crate::shared::util::binaries::{print_binaries, stored_targets};
 use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::{auto_append_rss, print_json};
+use serde_json::json;
 use std::path::{Path, PathBuf};
 
 pub fn stats<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
@@ -21,7 +24,14 @@ pub fn stats<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
         .ok_or("E79 Failed to directory name")?
         .to_string_lossy();
 
+    if *JSON_OUTPUT.get().unwrap() {
+        let mut json = path_contents.stats_json(&file_name);
+        json["binaries"] = json!(stored_targets(&path_contents)?);
+        return print_json(&json);
+    }
+
     path_contents.print_stats(&file_name);
+    print_binaries(&path_contents)?;
 
     Ok(())
 }
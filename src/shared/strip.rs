@@ -1,16 +1,20 @@
 use crate::shared::config::Config;
 use crate::shared::util::auto_append_rss;
 use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::HISTORY_SECTION;
+use crate::shared::util::lock::RssLock;
 use crate::time;
+use color_print::cprintln;
 use std::path::{Path, PathBuf};
 
-pub fn strip<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
+pub fn strip<P: AsRef<Path>>(config: &Config, path: P, history: bool) -> Result<(), String> {
     let path = if path.as_ref().is_file() {
         PathBuf::from(path.as_ref())
     } else {
         auto_append_rss(path, config)
     };
 
+    let lock = RssLock::acquire(&path)?;
     let mut path_contents = time!(
         "Reading file",
         false,
@@ -19,6 +23,14 @@ pub fn strip<P: AsRef<Path>>(config: &Config, path: P) -> Result<(), String> {
     );
 
     path_contents.remove_binary();
+    if history {
+        path_contents.set_section(HISTORY_SECTION, None);
+    }
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - file has not been stripped</>");
+        return Ok(());
+    };
 
     time!(
         "Saving stripped file",
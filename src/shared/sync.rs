@@ -0,0 +1,206 @@
+use crate::shared::TARGET_TRIPLE;
+use crate::shared::config::Config;
+use crate::shared::util::edit_recompile_shared::build_binaries;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::history::record_history;
+use crate::shared::util::lock::RssLock;
+use crate::shared::util::zip::{ZipEntry, zip_entries_to_bytes};
+use crate::time;
+use color_print::cprintln;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Marker file written to directories extracted with `rss extract --link`
+pub const LINK_FILE: &str = ".rss-link.json";
+
+/// The rss file a linked directory was extracted from, and the hash of its project when it was
+/// extracted (or last synced)
+#[derive(Serialize, Deserialize)]
+struct Link {
+    origin: PathBuf,
+    hash: String,
+}
+
+fn project_hash(file_contents: &FileContents) -> String {
+    format!("{:x}", Sha256::digest(file_contents.zipped_contents()))
+}
+
+fn save_link(dir: &Path, link: &Link) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(link)
+        .map_err(|e| format!("E213 Failed to serialise link: {e}"))?;
+    fs::write(dir.join(LINK_FILE), json)
+        .map_err(|e| format!("E214 Failed to write '{LINK_FILE}': {e}"))
+}
+
+/// Records `origin` in a link file in `dir` so `rss sync` can write changes back to it
+pub fn write_link(dir: &Path, origin: &Path, file_contents: &FileContents) -> Result<(), String> {
+    let link = Link {
+        origin: origin
+            .canonicalize()
+            .map_err(|e| format!("E212 Error parsing path: {e}"))?,
+        hash: project_hash(file_contents),
+    };
+    save_link(dir, &link)
+}
+
+/// Finds the linked directory containing `start`
+fn find_link(start: &Path) -> Result<(PathBuf, Link), String> {
+    let start = start
+        .canonicalize()
+        .map_err(|e| format!("E212 Error parsing path: {e}"))?;
+    let dir = start
+        .ancestors()
+        .find(|d| d.join(LINK_FILE).is_file())
+        .ok_or(format!(
+            "E215 '{}' is not in a directory extracted with `rss extract --link`",
+            start.to_string_lossy()
+        ))?;
+
+    let json = fs::read_to_string(dir.join(LINK_FILE))
+        .map_err(|e| format!("E216 Failed to read '{LINK_FILE}': {e}"))?;
+    let link = serde_json::from_str(&json)
+        .map_err(|e| format!("E217 Failed to parse '{LINK_FILE}': {e}"))?;
+    Ok((dir.to_path_buf(), link))
+}
+
+/// Reads the project in a linked directory, skipping `.git` directories, the `target` directory
+/// of each crate and the link file (as with `rss pack`)
+fn read_project_entries(dir: &Path) -> Result<Vec<ZipEntry>, String> {
+    let mut entries = Vec::new();
+    let walker = WalkDir::new(dir).into_iter().filter_entry(|e| {
+        let is_target = e.file_name() == "target"
+            && e.file_type().is_dir()
+            && (e.depth() == 1 || e.path().with_file_name("Cargo.toml").is_file());
+        e.file_name() != ".git" && !is_target && !(e.depth() == 1 && e.file_name() == LINK_FILE)
+    });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("E122 Failed to read directory: {e}"))?;
+        if entry.depth() == 0 {
+            continue;
+        }
+        let name = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        #[cfg(unix)]
+        let unix_mode = {
+            use std::os::unix::fs::PermissionsExt;
+            entry.metadata().ok().map(|m| m.permissions().mode())
+        };
+        #[cfg(windows)]
+        let unix_mode = None;
+
+        if entry.file_type().is_dir() {
+            entries.push(ZipEntry {
+                name: format!("{name}/"),
+                contents: Vec::new(),
+                unix_mode,
+                directory: true,
+            });
+        } else if entry.file_type().is_file() {
+            let contents =
+                fs::read(entry.path()).map_err(|e| format!("E123 Failed to read file: {e}"))?;
+            entries.push(ZipEntry {
+                name,
+                contents,
+                unix_mode,
+                directory: false,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+pub fn sync(config: &Config, dir: Option<&str>, force: bool) -> Result<(), String> {
+    let start = match dir {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::current_dir()
+            .map_err(|e| format!("E218 Failed to get current directory: {e}"))?,
+    };
+    let (dir, mut link) = find_link(&start)?;
+
+    let lock = RssLock::acquire(&link.origin)?;
+    let origin_contents = FileContents::from_path(&link.origin)?.ok_or(format!(
+        "E219 File contents not found: '{}'",
+        link.origin.to_string_lossy()
+    ))?;
+
+    if project_hash(&origin_contents) != link.hash && !force {
+        return Err(format!(
+            "E220 '{}' has changed since it was extracted - use `--force` to overwrite it",
+            link.origin.to_string_lossy()
+        ));
+    }
+
+    let file_name = link
+        .origin
+        .file_stem()
+        .ok_or("E221 Error parsing file name")?
+        .to_string_lossy()
+        .to_string();
+
+    // Built in place so the `target` directory is reused between syncs. A failed build is an
+    // error rather than opening an editor - fix the project in the directory and sync again.
+    let binary = if config.never_save_binary() {
+        None
+    } else {
+        Some(build_binaries(config, &dir, &file_name)?)
+    };
+
+    // Read after building so a `Cargo.lock` written by the build is included
+    let entries = time!("Reading project", false, read_project_entries(&dir)?);
+    let mut new_contents = origin_contents.clone();
+    new_contents.replace_project(time!(
+        "Zipping project",
+        false,
+        zip_entries_to_bytes(&entries)?
+    ));
+
+    match binary {
+        Some(binary) => new_contents.replace_binaries(TARGET_TRIPLE, &binary)?,
+        None => {
+            if !origin_contents.bin_contents().is_empty() {
+                cprintln!(
+                    "<yellow, bold>Removing the outdated binary - it will be rebuilt when next run</>"
+                );
+            }
+            new_contents.remove_binary();
+        }
+    }
+
+    record_history(
+        config,
+        Some(&origin_contents),
+        &mut new_contents,
+        Some(format!("Synced from '{}'", dir.to_string_lossy())),
+    )?;
+
+    let Some(path) = lock.save_path()? else {
+        cprintln!("<red, bold>Aborted - changes have not been saved</>");
+        return Ok(());
+    };
+
+    time!(
+        "Writing rss file",
+        false,
+        new_contents.save(&path, config)?;
+    );
+
+    // Changes saved to a side file haven't reached the origin, so it is still out of date
+    if path == link.origin {
+        link.hash = project_hash(&new_contents);
+        save_link(&dir, &link)?;
+    }
+
+    new_contents.print_stats(&path.to_string_lossy());
+
+    Ok(())
+}
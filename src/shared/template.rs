@@ -0,0 +1,169 @@
+use crate::shared::JSON_OUTPUT;
+use crate::shared::config::{Config, set_layer_value, unset_layer_value, warn_overridden};
+use crate::shared::diff::{as_text, read_dir_files};
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::print_json;
+use crate::shared::util::zip::{
+    ZipEntry, read_zip_entries, unzip_from_bytes, zip_entries_to_bytes,
+};
+use color_print::cprintln;
+use directories::BaseDirs;
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+
+const NAME_PLACEHOLDER: &str = "{{name}}";
+
+/// Expands a leading `~` in a template path to the home directory
+fn expand_home(path: &str) -> Result<PathBuf, String> {
+    let Some(rest) = path
+        .strip_prefix("~/")
+        .or_else(|| path.strip_prefix("~\\"))
+        .or((path == "~").then_some(""))
+    else {
+        return Ok(PathBuf::from(path));
+    };
+    let home = BaseDirs::new()
+        .map(|bd| bd.home_dir().to_owned())
+        .ok_or("E144 Failed to get home directory")?;
+    Ok(home.join(rest))
+}
+
+/// Reads the files of a template, which is either a project directory or an rss file
+fn read_template(path: &Path) -> Result<Vec<ZipEntry>, String> {
+    if path.is_dir() {
+        return Ok(read_dir_files(path)?
+            .into_iter()
+            .filter(|(name, _)| !name.starts_with(".git/"))
+            .map(|(name, contents)| ZipEntry {
+                name,
+                contents,
+                unix_mode: None,
+                directory: false,
+            })
+            .collect());
+    }
+
+    let path_contents = FileContents::from_path(path)?.ok_or(format!(
+        "E145 Template '{}' is not a directory or rss file",
+        path.to_string_lossy()
+    ))?;
+    read_zip_entries(path_contents.zipped_contents())
+}
+
+/// Creates a project in `dir` from the template `template_name`, replacing `{{name}}` in text
+/// files with `project_name`
+pub fn apply_template<P: AsRef<Path>>(
+    config: &Config,
+    template_name: &str,
+    dir: P,
+    project_name: &str,
+) -> Result<(), String> {
+    let template_path = config.templates().get(template_name).ok_or(format!(
+        "E146 Template '{template_name}' not found - list templates with `rss template ls`"
+    ))?;
+    let template_path = expand_home(template_path)?;
+
+    let mut entries = read_template(&template_path)?;
+    if !entries.iter().any(|e| e.name == "Cargo.toml") {
+        return Err(format!(
+            "E147 Template '{template_name}' does not contain a Cargo.toml"
+        ));
+    }
+
+    for entry in &mut entries {
+        if let Some(text) = as_text(&entry.contents)
+            && text.contains(NAME_PLACEHOLDER)
+        {
+            entry.contents = text.replace(NAME_PLACEHOLDER, project_name).into_bytes();
+        }
+    }
+
+    unzip_from_bytes(&zip_entries_to_bytes(&entries)?, dir.as_ref())
+}
+
+pub fn template_ls(config: &Config) -> Result<(), String> {
+    if *JSON_OUTPUT.get().unwrap() {
+        return print_json(&Value::Array(
+            config
+                .templates()
+                .iter()
+                .map(|(name, path)| {
+                    json!({
+                        "name": name,
+                        "path": path,
+                        "exists": expand_home(path).is_ok_and(|p| p.exists()),
+                    })
+                })
+                .collect(),
+        ));
+    }
+
+    if config.templates().is_empty() {
+        cprintln!("<yellow, bold>No templates found</>");
+        cprintln!("<cyan>Add one with <blue, bold>`rss template add <<name> <<path>`</></>");
+        return Ok(());
+    }
+
+    for (name, path) in config.templates() {
+        let exists = expand_home(path).is_ok_and(|p| p.exists());
+        if exists {
+            cprintln!("<green, bold>{}</>  {}", name, path);
+        } else {
+            cprintln!(
+                "<green, bold>{}</>  {} <red, bold>(not found)</>",
+                name,
+                path
+            );
+        }
+    }
+
+    Ok(())
+}
+
+pub fn template_add(config: &Config, name: &str, path: &str) -> Result<(), String> {
+    if name.contains('.') {
+        return Err(format!("E251 Template name '{name}' can't contain '.'"));
+    }
+    let expanded = expand_home(path)?;
+    read_template(&expanded)?;
+
+    // Keep `~` paths as they are so the config can be shared between machines
+    let stored = if path.starts_with('~') {
+        path.to_owned()
+    } else {
+        expanded
+            .canonicalize()
+            .map_err(|e| format!("E148 Failed to resolve template path: {e}"))?
+            .to_string_lossy()
+            .to_string()
+    };
+
+    // Only the template's own key is written, so templates from other layers aren't copied into
+    // the config file
+    let key = format!("templates.{name}");
+    let layer = set_layer_value(&key, Value::String(stored.clone()))?;
+
+    if config.templates().contains_key(name) {
+        cprintln!("Updated template <green, bold>{}</> to '{}'", name, stored);
+    } else {
+        cprintln!("Added template <green, bold>{}</> ('{}')", name, stored);
+    }
+    warn_overridden(config, &key, &layer);
+    Ok(())
+}
+
+pub fn template_rm(config: &Config, name: &str) -> Result<(), String> {
+    let key = format!("templates.{name}");
+    let (layer, removed) = unset_layer_value(&key)?;
+    if !removed {
+        return Err(match config.origins().get("templates") {
+            Some(origin) if config.templates().contains_key(name) => {
+                format!("E252 Template '{name}' is set in {origin}, not {layer} - remove it there")
+            }
+            _ => format!("E149 Template '{name}' not found"),
+        });
+    }
+    cprintln!("Removed template <green, bold>{}</>", name);
+    warn_overridden(config, &key, &layer);
+    Ok(())
+}
@@ -1,7 +1,10 @@
+use crate::shared::JSON_OUTPUT;
 use crate::shared::config::Config;
-use crate::shared::util::auto_append_rss;
+use crate::shared::util::binaries::{print_binaries, stored_targets};
 use crate::shared::util::file_contents::FileContents;
-use crate::shared::util::zip::print_tree;
+use crate::shared::util::zip::{files_json, print_tree};
+use crate::shared::util::{auto_append_rss, print_json};
+use serde_json::json;
 use std::path::{Path, PathBuf};
 
 pub fn tree<P: AsRef<Path>>(config: &Config, path: P, show_hidden: bool) -> Result<(), String> {
@@ -22,7 +25,16 @@ pub fn tree<P: AsRef<Path>>(config: &Config, path: P, show_hidden: bool) -> Resu
         path.as_path().to_string_lossy()
     ))?;
 
+    if *JSON_OUTPUT.get().unwrap() {
+        return print_json(&json!({
+            "file": file_name,
+            "files": files_json(path_contents.zipped_contents(), show_hidden)?,
+            "binaries": stored_targets(&path_contents)?,
+        }));
+    }
+
     print_tree(path_contents.zipped_contents(), &file_name, show_hidden)?;
+    print_binaries(&path_contents)?;
 
     Ok(())
 }
@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Path of the `n`th most recent backup of an rss file (e.g. `myfile.rss` -> `myfile.1.rss.bak`)
+pub fn backup_path<P: AsRef<Path>>(path: P, n: usize) -> PathBuf {
+    let path = path.as_ref();
+    match path.extension() {
+        Some(extension) => path.with_extension(format!("{n}.{}.bak", extension.to_string_lossy())),
+        None => path.with_extension(format!("{n}.bak")),
+    }
+}
+
+/// Shifts existing backups back by one (dropping any beyond `count`) and copies the current file
+/// into the most recent backup slot
+pub fn rotate_backups<P: AsRef<Path>>(path: P, count: usize) -> Result<(), String> {
+    let path = path.as_ref();
+    if count == 0 || !path.is_file() {
+        return Ok(());
+    }
+
+    let oldest = backup_path(path, count);
+    if oldest.is_file() {
+        fs::remove_file(&oldest).map_err(|e| format!("E95 Failed to remove old backup: {e}"))?;
+    }
+
+    for n in (1..count).rev() {
+        let from = backup_path(path, n);
+        if from.is_file() {
+            fs::rename(&from, backup_path(path, n + 1))
+                .map_err(|e| format!("E96 Failed to rotate backup: {e}"))?;
+        }
+    }
+
+    fs::copy(path, backup_path(path, 1))
+        .map_err(|e| format!("E97 Failed to create backup: {e}"))?;
+
+    Ok(())
+}
+
+/// Lists existing backups from most to least recent
+pub fn list_backups<P: AsRef<Path>>(path: P) -> Vec<(usize, PathBuf)> {
+    let mut backups = Vec::new();
+    let mut n = 1;
+    loop {
+        let backup = backup_path(path.as_ref(), n);
+        if !backup.is_file() {
+            break;
+        }
+        backups.push((n, backup));
+        n += 1;
+    }
+    backups
+}
@@ -0,0 +1,374 @@
+use crate::shared::util::file_contents::FileContents;
+use color_print::cprintln;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::process::Command;
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub const BINARIES_SECTION: &str = "binaries";
+
+const INDEX_FILE: &str = "binaries.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetKind {
+    Bin,
+    Example,
+}
+
+/// A `[[bin]]` or `[[example]]` target of a project
+#[derive(Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+pub struct BinaryTarget {
+    pub kind: TargetKind,
+    pub name: String,
+}
+
+impl BinaryTarget {
+    pub fn bin(name: &str) -> BinaryTarget {
+        BinaryTarget {
+            kind: TargetKind::Bin,
+            name: name.to_owned(),
+        }
+    }
+
+    pub fn example(name: &str) -> BinaryTarget {
+        BinaryTarget {
+            kind: TargetKind::Example,
+            name: name.to_owned(),
+        }
+    }
+
+    fn file_name(&self) -> String {
+        match self.kind {
+            TargetKind::Bin => format!("bin/{}", self.name),
+            TargetKind::Example => format!("example/{}", self.name),
+        }
+    }
+
+    /// Path of the built executable within a cargo profile directory (e.g. `target/release`)
+    fn built_path(&self, profile_dir: &Path) -> std::path::PathBuf {
+        #[cfg(unix)]
+        let file_name = self.name.clone();
+        #[cfg(windows)]
+        let file_name = format!("{}.exe", self.name);
+        match self.kind {
+            TargetKind::Bin => profile_dir.join(file_name),
+            TargetKind::Example => profile_dir.join("examples").join(file_name),
+        }
+    }
+}
+
+impl Display for BinaryTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            TargetKind::Bin => write!(f, "bin '{}'", self.name),
+            TargetKind::Example => write!(f, "example '{}'", self.name),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BinariesIndex {
+    default: BinaryTarget,
+    others: Vec<BinaryTarget>,
+}
+
+/// The executables built from a project. The default binary is stored in the main binary part
+/// of an rss file and any others in the binaries section.
+pub struct Binaries {
+    pub default: BinaryTarget,
+    pub default_binary: Vec<u8>,
+    pub others: Vec<(BinaryTarget, Vec<u8>)>,
+    /// Whether the default binary is the bin named after the rss file
+    pub implicit_default: bool,
+}
+
+impl Binaries {
+    /// Returns the binary for `target` (or the default binary if `None`)
+    pub fn get(&self, target: Option<&BinaryTarget>) -> Option<&[u8]> {
+        match target {
+            None => Some(&self.default_binary),
+            Some(target) if *target == self.default => Some(&self.default_binary),
+            Some(target) => self
+                .others
+                .iter()
+                .find(|(t, _)| t == target)
+                .map(|(_, b)| b.as_slice()),
+        }
+    }
+
+    pub fn targets(&self) -> impl Iterator<Item = &BinaryTarget> {
+        [&self.default]
+            .into_iter()
+            .chain(self.others.iter().map(|(t, _)| t))
+    }
+
+    /// Serialises the names of all binaries and the non-default binaries for the binaries
+    /// section, or `None` if the only binary is the bin named after the rss file so the file
+    /// needs no section (and keeps a layout older versions can read)
+    pub fn to_section(&self) -> Result<Option<Vec<u8>>, String> {
+        if self.others.is_empty() && self.implicit_default {
+            return Ok(None);
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        let options: FileOptions<()> =
+            FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip_writer = ZipWriter::new(&mut buffer);
+
+        let index = serde_json::to_vec(&BinariesIndex {
+            default: self.default.clone(),
+            others: self.others.iter().map(|(t, _)| t.clone()).collect(),
+        })
+        .map_err(|e| format!("E202 Failed to serialise binaries: {e}"))?;
+        zip_writer
+            .start_file(INDEX_FILE, options)
+            .and_then(|_| Ok(zip_writer.write_all(&index)?))
+            .map_err(|e| format!("E203 Failed to write binaries: {e}"))?;
+
+        for (target, binary) in &self.others {
+            zip_writer
+                .start_file(target.file_name(), options)
+                .and_then(|_| Ok(zip_writer.write_all(binary)?))
+                .map_err(|e| format!("E203 Failed to write binaries: {e}"))?;
+        }
+
+        zip_writer
+            .finish()
+            .map_err(|e| format!("E203 Failed to write binaries: {e}"))?;
+        Ok(Some(buffer.into_inner()))
+    }
+}
+
+type SectionArchive<'a> = ZipArchive<Cursor<&'a [u8]>>;
+
+fn read_index(section: &[u8]) -> Result<(SectionArchive<'_>, BinariesIndex), String> {
+    let mut archive = ZipArchive::new(Cursor::new(section))
+        .map_err(|e| format!("E204 Failed to open binaries: {e}"))?;
+    let mut index = Vec::new();
+    archive
+        .by_name(INDEX_FILE)
+        .and_then(|mut f| Ok(f.read_to_end(&mut index)?))
+        .map_err(|e| format!("E205 Failed to read binaries: {e}"))?;
+    let index =
+        serde_json::from_slice(&index).map_err(|e| format!("E205 Failed to read binaries: {e}"))?;
+    Ok((archive, index))
+}
+
+/// Lists the binaries stored in an rss file (default first), or `None` if the file only has an
+/// unnamed default binary
+pub fn stored_targets(file_contents: &FileContents) -> Result<Option<Vec<BinaryTarget>>, String> {
+    let Some(section) = file_contents.section(BINARIES_SECTION) else {
+        return Ok(None);
+    };
+    let (_, index) = read_index(section)?;
+    Ok(Some(
+        [index.default].into_iter().chain(index.others).collect(),
+    ))
+}
+
+/// Reads the binary for `target` (or the default binary if `None`) from the rss file named
+/// `file_name`
+pub fn stored_binary(
+    file_contents: &FileContents,
+    file_name: &str,
+    target: Option<&BinaryTarget>,
+) -> Result<Option<Vec<u8>>, String> {
+    if file_contents.bin_contents().is_empty() {
+        return Ok(None);
+    }
+    let Some(target) = target else {
+        return Ok(Some(file_contents.bin_contents().to_vec()));
+    };
+    let Some(section) = file_contents.section(BINARIES_SECTION) else {
+        // Without a section the only binary is the bin named after the file, or one built before
+        // binaries were named
+        return Ok((*target == BinaryTarget::bin(file_name))
+            .then(|| file_contents.bin_contents().to_vec()));
+    };
+
+    let (mut archive, index) = read_index(section)?;
+    if index.default == *target {
+        return Ok(Some(file_contents.bin_contents().to_vec()));
+    }
+    if !index.others.contains(target) {
+        return Ok(None);
+    }
+    let mut binary = Vec::new();
+    archive
+        .by_name(&target.file_name())
+        .and_then(|mut f| Ok(f.read_to_end(&mut binary)?))
+        .map_err(|e| format!("E205 Failed to read binaries: {e}"))?;
+    Ok(Some(binary))
+}
+
+pub fn missing_target_error<'a, I: Iterator<Item = &'a BinaryTarget>>(
+    target: &BinaryTarget,
+    available: I,
+) -> String {
+    format!(
+        "E206 Project has no {target} - available binaries: {}",
+        available
+            .map(|t| t.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    )
+}
+
+/// Prints the binaries stored in an rss file, if there is more than one
+pub fn print_binaries(file_contents: &FileContents) -> Result<(), String> {
+    let Some(targets) = stored_targets(file_contents)? else {
+        return Ok(());
+    };
+    if targets.len() < 2 {
+        return Ok(());
+    }
+    cprintln!(
+        "Binaries (run with <cyan>`rss run --bin <<name>`</> / <cyan>`--example <<name>`</>):"
+    );
+    for (i, target) in targets.iter().enumerate() {
+        let kind = match target.kind {
+            TargetKind::Bin => "bin",
+            TargetKind::Example => "example",
+        };
+        if i == 0 {
+            cprintln!("  - {} <green, bold>{}</> (default)", kind, target.name);
+        } else {
+            cprintln!("  - {} <green, bold>{}</>", kind, target.name);
+        }
+    }
+    Ok(())
+}
+
+/// The bin and example targets of a project (or workspace) and the name of the binary that
+/// should be the default
+pub struct ProjectTargets {
+    pub targets: Vec<BinaryTarget>,
+    pub default_run: Option<String>,
+}
+
+impl ProjectTargets {
+    pub fn has_examples(&self) -> bool {
+        self.targets.iter().any(|t| t.kind == TargetKind::Example)
+    }
+}
+
+/// Lists the executable targets of the project in `dir` with `cargo metadata`
+pub fn project_targets(dir: &Path) -> Result<ProjectTargets, String> {
+    let output = Command::new("cargo")
+        .args(["metadata", "--format-version", "1", "--no-deps"])
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("E185 Failed to run `cargo metadata`: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "E186 `cargo metadata` failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("E187 Failed to parse `cargo metadata` output: {e}"))?;
+
+    let root_manifest = dir.join("Cargo.toml").canonicalize().ok();
+    let mut targets = Vec::new();
+    let mut default_run = None;
+    for package in metadata["packages"].as_array().into_iter().flatten() {
+        let is_root = package["manifest_path"]
+            .as_str()
+            .and_then(|m| Path::new(m).canonicalize().ok())
+            .is_some_and(|m| Some(m) == root_manifest);
+        if is_root {
+            default_run = package["default_run"].as_str().map(|d| d.to_owned());
+        }
+
+        for target in package["targets"].as_array().into_iter().flatten() {
+            let Some(name) = target["name"].as_str() else {
+                continue;
+            };
+            let kinds = target["kind"].as_array().into_iter().flatten();
+            for kind in kinds.filter_map(|k| k.as_str()) {
+                let target = match kind {
+                    "bin" => BinaryTarget::bin(name),
+                    "example" => BinaryTarget::example(name),
+                    _ => continue,
+                };
+                if !targets.contains(&target) {
+                    targets.push(target);
+                }
+            }
+        }
+    }
+
+    Ok(ProjectTargets {
+        targets,
+        default_run,
+    })
+}
+
+/// Reads the executables built into `profile_dir` (e.g. `target/release`), choosing the default
+/// binary in order of: a bin named `file_name`, the root package's `default-run`, the only bin
+pub fn read_built_binaries(
+    profile_dir: &Path,
+    project_targets: &ProjectTargets,
+    file_name: &str,
+) -> Result<Binaries, String> {
+    let bins = project_targets
+        .targets
+        .iter()
+        .filter(|t| t.kind == TargetKind::Bin)
+        .collect::<Vec<_>>();
+
+    let default = bins
+        .iter()
+        .find(|t| t.name == file_name)
+        .or_else(|| {
+            project_targets
+                .default_run
+                .as_ref()
+                .and_then(|d| bins.iter().find(|t| &t.name == d))
+        })
+        .or_else(|| if bins.len() == 1 { bins.first() } else { None })
+        .ok_or(format!(
+            "E253 Could not choose a default binary from {} - name one `{file_name}` or set \
+            `default-run` in Cargo.toml",
+            if bins.is_empty() {
+                "(no binaries)".to_owned()
+            } else {
+                bins.iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        ))?;
+
+    let default_path = default.built_path(profile_dir);
+    let default_binary = fs::read(&default_path).map_err(|e| {
+        format!(
+            "E254 Failed to find built binary at path {:?} ({e})",
+            default_path
+        )
+    })?;
+
+    let mut others = Vec::new();
+    for target in &project_targets.targets {
+        if target == *default {
+            continue;
+        }
+        match fs::read(target.built_path(profile_dir)) {
+            Ok(binary) => others.push((target.clone(), binary)),
+            Err(e) => cprintln!("<yellow, bold>Not storing {}</> ({e})", target),
+        }
+    }
+
+    Ok(Binaries {
+        default: (*default).clone(),
+        default_binary,
+        others,
+        implicit_default: default.kind == TargetKind::Bin && default.name == file_name,
+    })
+}
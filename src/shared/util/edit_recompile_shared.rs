@@ -1,10 +1,11 @@
+use crate::shared::config::edit_command::EditContext;
 use crate::shared::config::{Config, get_config_path};
 use crate::shared::interruptable_command::InterruptableCommand;
+use crate::shared::util::binaries::{Binaries, project_targets, read_built_binaries};
 use crate::shared::util::file_contents::FileContents;
 use crate::shared::util::zip::unzip_from_bytes;
 use crate::time;
 use color_print::cprintln;
-use std::fs;
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
@@ -53,6 +54,69 @@ pub enum EditLoopMode {
     Install,
 }
 
+/// Runs the project edit command on the project in `temp_dir_string`, waiting for it to exit
+pub fn open_editor(config: &Config, temp_dir_string: &str, rss_path: &Path) -> Result<(), String> {
+    if let Err(e) = config.project_edit_command()?.run_command(&EditContext {
+        path: temp_dir_string,
+        rss: Some(rss_path),
+    })? {
+        return Err(format!(
+            "E49 Error when running project edit command: {}\n\
+        Check/edit the command used in '{}'.\n  - \
+        If you have your config edit program correctly configured use `rss config` to modify the config",
+            e,
+            get_config_path()?.as_os_str().to_string_lossy()
+        ));
+    }
+    Ok(())
+}
+
+/// Builds the binaries of the project in `temp_dir`, returning an error rather than prompting if
+/// the build fails
+pub fn build_binaries(
+    config: &Config,
+    temp_dir: &Path,
+    file_name: &str,
+) -> Result<Binaries, String> {
+    let targets = project_targets(temp_dir)?;
+    let mut args = vec!["build", "--workspace", "--bins"];
+    if targets.has_examples() {
+        args.push("--examples");
+    }
+    if config.use_debug_mode() {
+        println!("Building binary (debug)... ");
+    } else {
+        println!("Building binary (release)... ");
+        args.push("--release");
+    }
+
+    let output = Command::new("cargo")
+        .current_dir(temp_dir)
+        .args(&args)
+        .run_interruptable()?;
+
+    if !output.success() {
+        return Err(format!(
+            "E249 Cargo build failed {}",
+            output
+                .code()
+                .map_or_else(|| "with no code".to_string(), |c| format!("with code {c}"))
+        ));
+    }
+
+    let profile_dir = temp_dir.join("target").join(if config.use_debug_mode() {
+        "debug"
+    } else {
+        "release"
+    });
+    time!(
+        "Reading built binaries",
+        false,
+        read_built_binaries(&profile_dir, &targets, file_name)
+            .map_err(|e| format!("{e}\nIs a binary with a different name being built?"))
+    )
+}
+
 pub fn project_edit_loop<P: AsRef<Path>>(
     mut skip_first: bool,
     edit_loop_mode: EditLoopMode,
@@ -60,25 +124,12 @@ pub fn project_edit_loop<P: AsRef<Path>>(
     temp_dir: P,
     temp_dir_string: &str,
     file_name: &str,
-) -> Result<Option<Vec<u8>>, String> {
-    // TODO: Allow user to choose bin with bin-choice.txt file
-
+    rss_path: &Path,
+) -> Result<Option<Binaries>, String> {
     Ok(loop {
         if !skip_first {
             println!("Opening editor (and waiting for close)... ");
-
-            if let Err(e) = config
-                .rust_project_edit_command_blocking()
-                .run_command(Some(temp_dir_string))?
-            {
-                return Err(format!(
-                    "E49 Error when running project edit command: {}\n\
-                Check/edit the command used in '{}'.\n  - \
-                If you have your config edit program correctly configured use `rss config` to modify the config",
-                    e,
-                    get_config_path()?.as_os_str().to_string_lossy()
-                ));
-            }
+            open_editor(config, temp_dir_string, rss_path)?;
         }
         skip_first = false;
 
@@ -88,59 +139,9 @@ pub fn project_edit_loop<P: AsRef<Path>>(
                 return Ok(None);
             }
             EditLoopMode::CompileBinary => {
-                let args: &[&str] = if config.use_debug_mode() {
-                    println!("Building binary (debug)... ");
-                    &["build"]
-                } else {
-                    println!("Building binary (release)... ");
-                    &["build", "--release"]
-                };
-
-                let output = Command::new("cargo")
-                    .current_dir(temp_dir.as_ref())
-                    .args(args)
-                    .run_interruptable()?;
-                // let output =
-                //     output.map_err(|e| format!("E86 Error when running build command: {}", e))?;
-
-                if output.success() {
-                    let binary_path = temp_dir.as_ref().join("target");
-
-                    let binary_path = if config.use_debug_mode() {
-                        binary_path.join("debug")
-                    } else {
-                        binary_path.join("release")
-                    };
-
-                    #[cfg(unix)]
-                    let binary_path = binary_path.join(file_name);
-                    #[cfg(windows)]
-                    let binary_path = binary_path.join(format!("{file_name}.exe"));
-                    #[cfg(not(any(unix, windows)))]
-                    compile_error!("This crate only supports Unix or Windows targets.");
-
-                    let binary = time!("Reading built binary", false, fs::read(&binary_path));
-
-                    match binary {
-                        Ok(binary) => break Some(binary),
-                        Err(e) => {
-                            cprintln!(
-                                "<red, bold>Failed to find built binary at path {:?}</> ({e})",
-                                binary_path
-                            );
-                            cprintln!(
-                                "<cyan, bold>Is a binary with a different name being built?</>"
-                            );
-                        }
-                    };
-                } else {
-                    println!(
-                        "Cargo build failed {}",
-                        output.code().map_or_else(
-                            || "with no code".to_string(),
-                            |c| format!("with code {c}")
-                        )
-                    );
+                match build_binaries(config, temp_dir.as_ref(), file_name) {
+                    Ok(binaries) => break Some(binaries),
+                    Err(e) => cprintln!("<red, bold>{}</>", e),
                 }
             }
             EditLoopMode::Install => {
@@ -0,0 +1,470 @@
+use crate::time;
+use color_print::cprintln;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use tempfile::TempDir;
+use toml_edit::{Array, DocumentMut, InlineTable, Item, Table, Value as TomlValue};
+use walkdir::WalkDir;
+
+const DEPENDENCY_TABLES: [&str; 3] = ["dependencies", "dev-dependencies", "build-dependencies"];
+const EMBED_DIR: &str = "deps";
+
+/// A crate that is part of the packed project
+struct ProjectCrate {
+    source_dir: PathBuf,
+    /// Directory within the packed project (empty for the project itself)
+    output_dir: Vec<String>,
+    workspace_root: PathBuf,
+    /// Canonical source directories of path dependencies
+    path_deps: Vec<PathBuf>,
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, String> {
+    path.canonicalize().map_err(|e| {
+        format!(
+            "E184 Failed to resolve path '{}': {e}",
+            path.to_string_lossy()
+        )
+    })
+}
+
+/// Reads the workspace root and path dependencies of the crate in `dir` with `cargo metadata`
+fn crate_metadata(dir: &Path) -> Result<(PathBuf, Vec<PathBuf>), String> {
+    let manifest_path = dir.join("Cargo.toml");
+    let output = Command::new("cargo")
+        .args([
+            "metadata",
+            "--format-version",
+            "1",
+            "--no-deps",
+            "--manifest-path",
+        ])
+        .arg(&manifest_path)
+        .output()
+        .map_err(|e| format!("E185 Failed to run `cargo metadata`: {e}"))?;
+    if !output.status.success() {
+        return Err(format!(
+            "E186 `cargo metadata` failed for '{}': {}",
+            manifest_path.to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    let metadata: Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("E187 Failed to parse `cargo metadata` output: {e}"))?;
+
+    let workspace_root = metadata["workspace_root"]
+        .as_str()
+        .map(|r| canonicalize(Path::new(r)))
+        .ok_or("E187 Failed to parse `cargo metadata` output: missing workspace root")??;
+
+    // With `--no-deps` all workspace members are listed so find the one for this manifest (or
+    // use all of them for a virtual workspace manifest)
+    let packages = metadata["packages"].as_array().cloned().unwrap_or_default();
+    let manifest = canonicalize(&manifest_path)?;
+    let package = packages.iter().find(|p| {
+        p["manifest_path"]
+            .as_str()
+            .and_then(|m| Path::new(m).canonicalize().ok())
+            .is_some_and(|m| m == manifest)
+    });
+    let packages = match package {
+        Some(package) => vec![package],
+        None if workspace_root.join("Cargo.toml") == manifest => packages.iter().collect(),
+        None => {
+            return Err(format!(
+                "E188 `cargo metadata` did not list the package at '{}'",
+                manifest_path.to_string_lossy()
+            ));
+        }
+    };
+
+    let mut path_deps = Vec::new();
+    for package in packages {
+        for dependency in package["dependencies"].as_array().into_iter().flatten() {
+            if let Some(path) = dependency["path"].as_str() {
+                let path = canonicalize(Path::new(path))?;
+                if !path_deps.contains(&path) {
+                    path_deps.push(path);
+                }
+            }
+        }
+    }
+
+    Ok((workspace_root, path_deps))
+}
+
+/// Finds every crate reachable through path dependencies, deciding where each will be placed
+/// in the packed project
+fn find_crates(root: &Path) -> Result<Vec<ProjectCrate>, String> {
+    let mut crates: Vec<ProjectCrate> = Vec::new();
+    let mut queue = vec![(root.to_path_buf(), Vec::new())];
+
+    while let Some((source_dir, output_dir)) = queue.pop() {
+        let (workspace_root, path_deps) = crate_metadata(&source_dir)?;
+
+        for dep in &path_deps {
+            if crates.iter().any(|c| &c.source_dir == dep)
+                || queue.iter().any(|(d, _)| d == dep)
+                || dep == &source_dir
+            {
+                continue;
+            }
+
+            // Crates within the project are packed where they are
+            let dep_output_dir = if let Ok(inner) = dep.strip_prefix(root) {
+                inner
+                    .iter()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .collect()
+            } else {
+                let name = dep
+                    .file_name()
+                    .map_or("dep".to_owned(), |n| n.to_string_lossy().to_string());
+                let taken = |n: &str| {
+                    crates
+                        .iter()
+                        .map(|c| &c.output_dir)
+                        .chain(queue.iter().map(|(_, o)| o))
+                        .any(|o| o.len() == 2 && o[0] == EMBED_DIR && o[1] == n)
+                };
+                let mut unique = name.clone();
+                let mut i = 2;
+                while taken(&unique) {
+                    unique = format!("{name}-{i}");
+                    i += 1;
+                }
+                vec![EMBED_DIR.to_owned(), unique]
+            };
+            queue.push((dep.clone(), dep_output_dir));
+        }
+
+        crates.push(ProjectCrate {
+            source_dir,
+            output_dir,
+            workspace_root,
+            path_deps,
+        });
+    }
+
+    Ok(crates)
+}
+
+fn uses_workspace_inheritance(manifest: &str) -> bool {
+    manifest
+        .lines()
+        .any(|l| l.replace(' ', "").contains("workspace=true"))
+}
+
+/// Relative path (using `/`) from one directory in the project to another
+fn relative_path(from: &[String], to: &[String]) -> String {
+    let common = from.iter().zip(to).take_while(|(a, b)| a == b).count();
+    let parts = std::iter::repeat_n("..".to_owned(), from.len() - common)
+        .chain(to[common..].iter().cloned())
+        .collect::<Vec<_>>();
+    if parts.is_empty() {
+        ".".to_owned()
+    } else {
+        parts.join("/")
+    }
+}
+
+fn is_inherited(item: &Item) -> bool {
+    item.get("workspace")
+        .and_then(|w| w.as_bool())
+        .unwrap_or(false)
+}
+
+fn to_inline_table(item: &Item) -> Option<InlineTable> {
+    match item {
+        Item::Value(TomlValue::String(version)) => {
+            let mut table = InlineTable::new();
+            table.insert("version", version.value().as_str().into());
+            Some(table)
+        }
+        Item::Value(TomlValue::InlineTable(table)) => Some(table.clone()),
+        Item::Table(table) => Some(table.clone().into_inline_table()),
+        _ => None,
+    }
+}
+
+/// Replaces a `{ workspace = true, ... }` dependency with the workspace's definition
+fn inline_dependency(
+    member: &Item,
+    workspace_dep: &Item,
+    workspace_root: &Path,
+) -> Result<Item, String> {
+    let mut inlined = to_inline_table(workspace_dep)
+        .ok_or("E189 Invalid dependency in `[workspace.dependencies]`")?;
+
+    // Paths in the workspace are relative to the workspace root
+    if let Some(path) = inlined.get("path").and_then(|p| p.as_str()) {
+        let path = workspace_root.join(path).to_string_lossy().to_string();
+        inlined.insert("path", path.into());
+    }
+
+    if let Some(member) = to_inline_table(member) {
+        for (key, value) in member.iter() {
+            match key {
+                "workspace" => {}
+                "features" => {
+                    let mut features = inlined
+                        .get("features")
+                        .and_then(|f| f.as_array())
+                        .cloned()
+                        .unwrap_or_else(Array::new);
+                    for feature in value.as_array().into_iter().flatten() {
+                        if !features.iter().any(|f| f.as_str() == feature.as_str()) {
+                            features.push(feature.clone());
+                        }
+                    }
+                    inlined.insert("features", TomlValue::Array(features));
+                }
+                _ => {
+                    inlined.insert(key, value.clone());
+                }
+            }
+        }
+    }
+
+    inlined.fmt();
+    Ok(Item::Value(TomlValue::InlineTable(inlined)))
+}
+
+/// Calls `f` on every dependency table (including target-specific ones)
+fn for_each_dependency_table<F: FnMut(&mut Table) -> Result<(), String>>(
+    document: &mut DocumentMut,
+    mut f: F,
+) -> Result<(), String> {
+    for name in DEPENDENCY_TABLES {
+        if let Some(table) = document.get_mut(name).and_then(|t| t.as_table_mut()) {
+            f(table)?;
+        }
+    }
+    if let Some(targets) = document.get_mut("target").and_then(|t| t.as_table_mut()) {
+        for (_, target) in targets.iter_mut() {
+            for name in DEPENDENCY_TABLES {
+                if let Some(table) = target.get_mut(name).and_then(|t| t.as_table_mut()) {
+                    f(table)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Inlines fields inherited from the workspace (`version.workspace = true`,
+/// `serde = { workspace = true }`, `[lints] workspace = true`)
+fn inline_workspace_fields(
+    document: &mut DocumentMut,
+    workspace_root: &Path,
+    crate_output: &Path,
+) -> Result<(), String> {
+    let workspace_manifest = fs::read_to_string(workspace_root.join("Cargo.toml"))
+        .map_err(|e| format!("E190 Failed to read workspace Cargo.toml: {e}"))?;
+    let workspace = workspace_manifest
+        .parse::<DocumentMut>()
+        .map_err(|e| format!("E191 Failed to parse workspace Cargo.toml: {e}"))?;
+    let workspace = workspace
+        .get("workspace")
+        .and_then(|w| w.as_table())
+        .ok_or("E192 Workspace Cargo.toml has no `[workspace]` table")?;
+
+    if let Some(package) = document.get_mut("package").and_then(|p| p.as_table_mut()) {
+        let inherited = package
+            .iter()
+            .filter(|(_, v)| is_inherited(v))
+            .map(|(k, _)| k.to_owned())
+            .collect::<Vec<_>>();
+        for key in inherited {
+            let value = workspace
+                .get("package")
+                .and_then(|p| p.get(&key))
+                .ok_or(format!(
+                    "E193 `package.{key}` is not defined in `[workspace.package]`"
+                ))?;
+            let mut value = value.clone();
+
+            // Files referenced by the workspace are copied next to the manifest
+            if matches!(key.as_str(), "readme" | "license-file")
+                && let Some(file) = value.as_str()
+            {
+                let source = workspace_root.join(file);
+                let file_name = source
+                    .file_name()
+                    .ok_or("E194 Invalid file path in `[workspace.package]`")?
+                    .to_string_lossy()
+                    .to_string();
+                fs::copy(&source, crate_output.join(&file_name))
+                    .map_err(|e| format!("E195 Failed to copy '{file}': {e}"))?;
+                value = toml_edit::value(file_name);
+            }
+
+            if let Item::Value(value) = &mut value {
+                value.decor_mut().clear();
+            }
+            package.insert(&key, value);
+        }
+    }
+
+    let workspace_deps = workspace.get("dependencies").and_then(|d| d.as_table());
+    for_each_dependency_table(document, |table| {
+        let inherited = table
+            .iter()
+            .filter(|(_, v)| is_inherited(v))
+            .map(|(k, _)| k.to_owned())
+            .collect::<Vec<_>>();
+        for name in inherited {
+            let workspace_dep = workspace_deps.and_then(|d| d.get(&name)).ok_or(format!(
+                "E196 Dependency '{name}' is not defined in `[workspace.dependencies]`"
+            ))?;
+            let inlined = inline_dependency(&table[&name], workspace_dep, workspace_root)?;
+            table.insert(&name, inlined);
+        }
+        Ok(())
+    })?;
+
+    if document.get("lints").is_some_and(is_inherited) {
+        let lints = workspace
+            .get("lints")
+            .ok_or("E197 `lints` is not defined in `[workspace]`")?
+            .clone();
+        document.insert("lints", lints);
+    }
+
+    Ok(())
+}
+
+/// Points path dependencies at where the crates have been placed within the packed project
+fn rewrite_dependency_paths(
+    document: &mut DocumentMut,
+    project_crate: &ProjectCrate,
+    crates: &[ProjectCrate],
+) -> Result<(), String> {
+    for_each_dependency_table(document, |table| {
+        for (_, dep) in table.iter_mut() {
+            let Some(path) = dep.get("path").and_then(|p| p.as_str()) else {
+                continue;
+            };
+            let Ok(source) = project_crate.source_dir.join(path).canonicalize() else {
+                continue;
+            };
+            if let Some(target) = crates.iter().find(|c| c.source_dir == source) {
+                dep["path"] =
+                    toml_edit::value(relative_path(&project_crate.output_dir, &target.output_dir));
+            }
+        }
+        Ok(())
+    })
+}
+
+fn copy_dir(source: &Path, destination: &Path) -> Result<(), String> {
+    let walker = WalkDir::new(source).into_iter().filter_entry(|e| {
+        !(e.depth() == 1 && e.file_name() == "target") && e.file_name() != ".git"
+    });
+    for entry in walker {
+        let entry = entry.map_err(|e| format!("E198 Failed to read directory: {e}"))?;
+        let target = destination.join(entry.path().strip_prefix(source).unwrap());
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&target)
+                .map_err(|e| format!("E199 Failed to create directory: {e}"))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target)
+                .map_err(|e| format!("E200 Failed to copy file: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// Makes a project that depends on crates outside its directory (`path = "../common"`) or
+/// inherits from a workspace (`version.workspace = true`) self-contained by copying it and its
+/// path dependencies to a temporary directory, then rewriting the manifests.
+///
+/// Returns `None` if the project is already self-contained.
+pub fn embed_path_dependencies<P: AsRef<Path>>(dir: P) -> Result<Option<TempDir>, String> {
+    let root = canonicalize(dir.as_ref())?;
+    let crates = time!("Finding path dependencies", false, find_crates(&root)?);
+
+    let mut manifests = BTreeMap::new();
+    for project_crate in &crates {
+        let manifest = fs::read_to_string(project_crate.source_dir.join("Cargo.toml"))
+            .map_err(|e| format!("E180 Failed to read Cargo.toml: {e}"))?;
+        manifests.insert(project_crate.source_dir.clone(), manifest);
+    }
+
+    let inherits_outside = |c: &ProjectCrate| {
+        !c.workspace_root.starts_with(&root)
+            && uses_workspace_inheritance(&manifests[&c.source_dir])
+    };
+    let embeds = crates.iter().any(|c| !c.source_dir.starts_with(&root));
+    if !embeds && !crates.iter().any(inherits_outside) {
+        return Ok(None);
+    }
+
+    let temp_dir =
+        TempDir::new().map_err(|e| format!("E05 Failed to create temp directory: {e}"))?;
+
+    for project_crate in &crates {
+        let output = project_crate
+            .output_dir
+            .iter()
+            .fold(temp_dir.path().to_path_buf(), |p, c| p.join(c));
+
+        if project_crate.output_dir.is_empty() {
+            copy_dir(&root, temp_dir.path())?;
+        } else if !project_crate.source_dir.starts_with(&root) {
+            cprintln!(
+                "Embedding path dependency <cyan>'{}'</> as <cyan>'{}'</>",
+                project_crate.source_dir.to_string_lossy(),
+                project_crate.output_dir.join("/")
+            );
+            copy_dir(&project_crate.source_dir, &output)?;
+        }
+    }
+
+    for project_crate in &crates {
+        let output = project_crate
+            .output_dir
+            .iter()
+            .fold(temp_dir.path().to_path_buf(), |p, c| p.join(c));
+
+        let mut document = manifests[&project_crate.source_dir]
+            .parse::<DocumentMut>()
+            .map_err(|e| format!("E181 Failed to parse Cargo.toml: {e}"))?;
+
+        if inherits_outside(project_crate) {
+            cprintln!(
+                "Inlining workspace fields into <cyan>'{}'</>",
+                project_crate
+                    .output_dir
+                    .iter()
+                    .chain(["Cargo.toml".to_owned()].iter())
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join("/")
+            );
+            inline_workspace_fields(&mut document, &project_crate.workspace_root, &output)?;
+
+            // Keep the versions locked by the workspace
+            let workspace_lock = project_crate.workspace_root.join("Cargo.lock");
+            if project_crate.output_dir.is_empty()
+                && !output.join("Cargo.lock").exists()
+                && workspace_lock.is_file()
+            {
+                fs::copy(&workspace_lock, output.join("Cargo.lock"))
+                    .map_err(|e| format!("E200 Failed to copy file: {e}"))?;
+            }
+        }
+        if !project_crate.path_deps.is_empty() {
+            rewrite_dependency_paths(&mut document, project_crate, &crates)?;
+        }
+
+        fs::write(output.join("Cargo.toml"), document.to_string())
+            .map_err(|e| format!("E201 Failed to write Cargo.toml: {e}"))?;
+    }
+
+    Ok(Some(temp_dir))
+}
@@ -1,13 +1,19 @@
 use crate::shared::config::Config;
+use crate::shared::util::backup::rotate_backups;
+use crate::shared::util::binaries::{BINARIES_SECTION, Binaries};
 use crate::shared::util::executable::make_executable;
 use crate::shared::{RS_SCRIPT_VERSION, VERBOSE};
 use crate::time;
 use color_print::{cformat, cprint, cprintln};
 use human_bytes::human_bytes;
 use num_format::{Locale, ToFormattedString};
+use serde_json::{Value, json};
 use std::borrow::Cow;
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::Write;
 use std::path::Path;
+use tempfile::NamedTempFile;
 
 type LengthType = u64;
 const LENGTH_TYPE_SIZE: usize = size_of::<LengthType>();
@@ -16,9 +22,16 @@ const LENGTH_TYPE_SIZE: usize = size_of::<LengthType>();
 type LayoutVersionType = u32;
 const LAYOUT_VERSION_SIZE: usize = size_of::<LayoutVersionType>();
 
-// ! List of layout versions and newest rs-script version that supports them
+// ! List of layout versions and newest rs-script version that creates them
 // ! Last entry is the current layout version
-const LAYOUT_VERSIONS: [(LayoutVersionType, &str); 2] = [(1, "0.2.25"), (2, "0.3.8")];
+const LAYOUT_VERSIONS: [(LayoutVersionType, &str); 3] = [(1, "0.2.25"), (2, "0.3.8"), (3, "0.4.0")];
+
+// ! Files without sections are still written as v2 so older versions can read them
+const NO_SECTIONS_LAYOUT_VERSION: LayoutVersionType = 2;
+
+// ! Older layouts that can still be read (and are upgraded to the current layout when a section
+// ! is added)
+const READABLE_LAYOUT_VERSIONS: [LayoutVersionType; 1] = [2];
 
 #[allow(dead_code)]
 const fn version_check() -> bool {
@@ -32,7 +45,9 @@ const_assert!(version_check());
 const CURRENT_LAYOUT_VERSION: LayoutVersionType = LAYOUT_VERSIONS.last().unwrap().0;
 
 fn check_layout_version(layout_version: LayoutVersionType) -> Result<(), String> {
-    if layout_version != CURRENT_LAYOUT_VERSION {
+    if layout_version != CURRENT_LAYOUT_VERSION
+        && !READABLE_LAYOUT_VERSIONS.contains(&layout_version)
+    {
         let last_supported = LAYOUT_VERSIONS
             .iter()
             .find(|(v, _)| *v == layout_version)
@@ -61,53 +76,169 @@ fn check_layout_version(layout_version: LayoutVersionType) -> Result<(), String>
 
 const SHEBANG_TEXT: &[u8] = b"#!/usr/bin/env rss-run\n";
 
+// ? Layout (v3):
+// ? [shebang][zip len][zip][triple len][triple][binary len][binary]
+// ?     ([section name len][section name][section len][section])* [layout version]
+// ? Layout v2 is identical up to the triple, after which the binary takes up the remainder of the
+// ? file with no length or sections
+#[derive(Clone)]
 pub struct FileContents {
     layout_version: LayoutVersionType,
-    contents: Vec<u8>,
-    triple_length: usize,
-    zip_length: usize,
+    zipped_contents: Vec<u8>,
+    triple: String,
+    binary: Vec<u8>,
+    sections: Vec<(String, Vec<u8>)>,
+}
+
+/// Sizes of each part of an rss file in bytes, including length prefixes
+struct Sizes<'a> {
+    zip: usize,
+    triple: usize,
+    binary: usize,
+    sections: Vec<(&'a str, usize)>,
+    total: usize,
 }
 
 impl FileContents {
-    pub fn new(zipped_contents: Vec<u8>, binary_contents: Vec<u8>, triple: &str) -> Self {
-        let mut final_contents = Vec::new();
-        final_contents.extend_from_slice(SHEBANG_TEXT); // shebang
-        final_contents.extend_from_slice(&(zipped_contents.len() as LengthType).to_le_bytes()); // zipped len
-        final_contents.extend_from_slice(&zipped_contents); // zipped
-        final_contents.extend_from_slice(&(triple.len() as LengthType).to_le_bytes()); // triple len
-        final_contents.extend(triple.as_bytes()); // triple
-        final_contents.extend_from_slice(&binary_contents); // binary
-        final_contents.extend_from_slice(&CURRENT_LAYOUT_VERSION.to_le_bytes()); // Layout version
-        FileContents {
-            layout_version: CURRENT_LAYOUT_VERSION,
-            contents: final_contents,
-            triple_length: triple.len(),
-            zip_length: zipped_contents.len(),
+    pub fn new(
+        zipped_contents: Vec<u8>,
+        binaries: Option<&Binaries>,
+        triple: &str,
+    ) -> Result<Self, String> {
+        let mut file_contents = FileContents {
+            layout_version: NO_SECTIONS_LAYOUT_VERSION,
+            zipped_contents,
+            triple: triple.to_owned(),
+            binary: Vec::new(),
+            sections: Vec::new(),
+        };
+        if let Some(binaries) = binaries {
+            file_contents.replace_binaries(triple, binaries)?;
         }
+        Ok(file_contents)
     }
 
     pub fn remove_binary(&mut self) {
-        self.contents.truncate(
-            SHEBANG_TEXT.len()
-                + LENGTH_TYPE_SIZE
-                + self.zip_length
-                + LENGTH_TYPE_SIZE
-                + self.triple_length,
-        );
-        self.contents
-            .extend_from_slice(&CURRENT_LAYOUT_VERSION.to_le_bytes());
+        self.binary = Vec::new();
+        self.set_section(BINARIES_SECTION, None);
+    }
+
+    pub fn replace_binaries(&mut self, triple: &str, binaries: &Binaries) -> Result<(), String> {
+        self.triple = triple.to_owned();
+        self.binary = binaries.default_binary.clone();
+        self.set_section(BINARIES_SECTION, binaries.to_section()?);
+        Ok(())
+    }
+
+    pub fn replace_project(&mut self, zipped_contents: Vec<u8>) {
+        self.zipped_contents = zipped_contents;
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = (&str, &[u8])> {
+        self.sections
+            .iter()
+            .map(|(n, s)| (n.as_str(), s.as_slice()))
     }
 
-    pub fn replace_binary(&mut self, triple: &str, binary: &[u8]) {
-        self.contents
-            .truncate(SHEBANG_TEXT.len() + LENGTH_TYPE_SIZE + self.zip_length);
-        self.triple_length = triple.len();
-        self.contents
-            .extend_from_slice((triple.len() as LengthType).to_le_bytes().as_ref()); // triple len
-        self.contents.extend(triple.as_bytes()); // triple
-        self.contents.extend_from_slice(binary); // binary
-        self.contents
-            .extend_from_slice(&CURRENT_LAYOUT_VERSION.to_le_bytes());
+    pub fn section(&self, name: &str) -> Option<&[u8]> {
+        self.sections
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, s)| s.as_slice())
+    }
+
+    /// Sets the contents of a named section, removing it if `contents` is `None`
+    pub fn set_section(&mut self, name: &str, contents: Option<Vec<u8>>) {
+        let existing = self.sections.iter().position(|(n, _)| n == name);
+        match (existing, contents) {
+            (Some(i), Some(contents)) => self.sections[i].1 = contents,
+            (Some(i), None) => {
+                self.sections.remove(i);
+            }
+            (None, Some(contents)) => self.sections.push((name.to_owned(), contents)),
+            (None, None) => {}
+        }
+        self.layout_version = if self.sections.is_empty() {
+            NO_SECTIONS_LAYOUT_VERSION
+        } else {
+            CURRENT_LAYOUT_VERSION
+        };
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut contents = Vec::new();
+        contents.extend_from_slice(SHEBANG_TEXT); // shebang
+        contents.extend_from_slice(&(self.zipped_contents.len() as LengthType).to_le_bytes()); // zipped len
+        contents.extend_from_slice(&self.zipped_contents); // zipped
+        contents.extend_from_slice(&(self.triple.len() as LengthType).to_le_bytes()); // triple len
+        contents.extend(self.triple.as_bytes()); // triple
+        if self.layout_version == NO_SECTIONS_LAYOUT_VERSION {
+            contents.extend_from_slice(&self.binary); // binary (remainder of the file in v2)
+            contents.extend_from_slice(&NO_SECTIONS_LAYOUT_VERSION.to_le_bytes()); // Layout version
+            return contents;
+        }
+        contents.extend_from_slice(&(self.binary.len() as LengthType).to_le_bytes()); // binary len
+        contents.extend_from_slice(&self.binary); // binary
+        for (name, section) in &self.sections {
+            contents.extend_from_slice(&(name.len() as LengthType).to_le_bytes()); // section name len
+            contents.extend(name.as_bytes()); // section name
+            contents.extend_from_slice(&(section.len() as LengthType).to_le_bytes()); // section len
+            contents.extend_from_slice(section); // section
+        }
+        contents.extend_from_slice(&CURRENT_LAYOUT_VERSION.to_le_bytes()); // Layout version
+        contents
+    }
+
+    fn sizes(&self) -> Sizes<'_> {
+        let zip = LENGTH_TYPE_SIZE + self.zipped_contents.len();
+        // Binary length is counted as part of the target indicator so an empty binary is 0 B
+        let triple = if self.layout_version == NO_SECTIONS_LAYOUT_VERSION {
+            LENGTH_TYPE_SIZE + self.triple.len()
+        } else {
+            LENGTH_TYPE_SIZE + self.triple.len() + LENGTH_TYPE_SIZE
+        };
+        let binary = self.binary.len();
+        let sections = self
+            .sections
+            .iter()
+            .map(|(name, section)| {
+                let size = LENGTH_TYPE_SIZE + name.len() + LENGTH_TYPE_SIZE + section.len();
+                (name.as_str(), size)
+            })
+            .collect::<Vec<_>>();
+        let total = SHEBANG_TEXT.len()
+            + zip
+            + triple
+            + binary
+            + sections.iter().map(|(_, size)| size).sum::<usize>()
+            + LAYOUT_VERSION_SIZE;
+        Sizes {
+            zip,
+            triple,
+            binary,
+            sections,
+            total,
+        }
+    }
+
+    /// The information shown by `print_stats` for `--format json`
+    pub fn stats_json(&self, file_name: &str) -> Value {
+        let sizes = self.sizes();
+        json!({
+            "file": file_name,
+            "layout_version": self.layout_version,
+            "target_triple": self.target_triple(),
+            "has_binary": !self.binary.is_empty(),
+            "sizes": {
+                "shebang": SHEBANG_TEXT.len(),
+                "project_zip": sizes.zip,
+                "target_indicator": sizes.triple,
+                "binary": sizes.binary,
+                "sections": sizes.sections.into_iter().collect::<BTreeMap<_, _>>(),
+                "layout_indicator": LAYOUT_VERSION_SIZE,
+                "total": sizes.total,
+            },
+        })
     }
 
     pub fn print_stats(&self, file_name: &str) {
@@ -119,23 +250,24 @@ impl FileContents {
             self.target_triple()
         );
 
+        let Sizes {
+            zip: zip_size,
+            triple: triple_size,
+            binary: binary_size,
+            sections,
+            total: total_size,
+        } = self.sizes();
         let shebang_size_str = human_bytes(SHEBANG_TEXT.len() as f64);
-        let zip_size = LENGTH_TYPE_SIZE + self.zip_length;
         let zip_size_str = human_bytes(zip_size as f64);
-        let triple_size = LENGTH_TYPE_SIZE + self.triple_length;
         let triple_size_str = human_bytes(triple_size as f64);
-        let binary_size = self.contents.len()
-            - SHEBANG_TEXT.len()
-            - LENGTH_TYPE_SIZE
-            - self.zip_length
-            - LENGTH_TYPE_SIZE
-            - self.triple_length
-            - LAYOUT_VERSION_SIZE;
         let binary_size_str = human_bytes(binary_size as f64);
+        let section_sizes = sections
+            .into_iter()
+            .map(|(name, size)| (name, size, human_bytes(size as f64)))
+            .collect::<Vec<_>>();
         let layout_size_str = human_bytes(LAYOUT_VERSION_SIZE as f64);
-        let total_size = self.contents.len();
         let total_size_str = human_bytes(total_size as f64);
-        let max_len = *[
+        let max_len = [
             shebang_size_str.len(),
             zip_size_str.len(),
             triple_size_str.len(),
@@ -143,7 +275,8 @@ impl FileContents {
             total_size_str.len(),
             layout_size_str.len(),
         ]
-        .iter()
+        .into_iter()
+        .chain(section_sizes.iter().map(|(_, _, s)| s.len()))
         .max()
         .unwrap();
 
@@ -195,12 +328,26 @@ impl FileContents {
             verbose_bytes(binary_size, binary_size_str.len(), true)
         );
 
-        if binary_size == 0 {
+        if self.binary.is_empty() {
             cprintln!("<red> (no binary)</>");
         } else {
             println!();
         }
 
+        for (name, size, size_str) in &section_sizes {
+            let mut chars = name.chars();
+            let label = match chars.next() {
+                Some(first) => format!("{}{} size:", first.to_uppercase(), chars.as_str()),
+                None => "Unnamed section size:".to_owned(),
+            };
+            cprintln!(
+                "  - {:<24}<cyan>{}</>{}",
+                label,
+                size_str,
+                verbose_bytes(*size, size_str.len(), true)
+            );
+        }
+
         if verbose {
             cprintln!(
                 "  - Layout indicator size:  <cyan>{}</>{}",
@@ -255,75 +402,125 @@ impl FileContents {
         cursor += SHEBANG_TEXT.len();
 
         // ? Zip
-        if contents.len() < cursor + LENGTH_TYPE_SIZE {
-            return Err(format!("Corrupted file: {:?} [E1]", path.as_ref()));
-        }
-        let zipped_len = LengthType::from_le_bytes(
-            contents[cursor..cursor + LENGTH_TYPE_SIZE]
-                .try_into()
-                .unwrap(),
-        ) as usize;
-        cursor += LENGTH_TYPE_SIZE;
-        cursor += zipped_len;
+        let zipped_contents = read_length_prefixed(contents, &mut cursor)
+            .ok_or_else(|| format!("Corrupted file: {:?} [E1]", path.as_ref()))?
+            .to_vec();
 
         // ? Target triple
-        if contents.len() < cursor + LENGTH_TYPE_SIZE {
-            return Err(format!("Corrupted file: {:?} [E2]", path.as_ref()));
+        let triple = read_length_prefixed(contents, &mut cursor)
+            .ok_or_else(|| format!("Corrupted file: {:?} [E46]", path.as_ref()))?;
+        let triple = String::from_utf8_lossy(triple).to_string();
+
+        // ? Binary
+        if layout_version == 2 {
+            // Binary is the remainder of the file in v2
+            return Ok(Some(FileContents {
+                layout_version,
+                zipped_contents,
+                triple,
+                binary: contents[cursor..].to_vec(),
+                sections: Vec::new(),
+            }));
         }
-        let triple_len = LengthType::from_le_bytes(
-            contents[cursor..cursor + LENGTH_TYPE_SIZE]
-                .try_into()
-                .unwrap(),
-        ) as usize;
-        cursor += LENGTH_TYPE_SIZE;
-        if contents.len() < cursor + triple_len {
-            return Err(format!("Corrupted file: {:?} [E46]", path.as_ref()));
+        let binary = read_length_prefixed(contents, &mut cursor)
+            .ok_or_else(|| format!("Corrupted file: {:?} [E105]", path.as_ref()))?
+            .to_vec();
+
+        // ? Sections
+        let mut sections = Vec::new();
+        while cursor < contents.len() {
+            let name = read_length_prefixed(contents, &mut cursor)
+                .and_then(|n| String::from_utf8(n.to_vec()).ok())
+                .ok_or_else(|| format!("Corrupted file: {:?} [E106]", path.as_ref()))?;
+            let section = read_length_prefixed(contents, &mut cursor)
+                .ok_or_else(|| format!("Corrupted file: {:?} [E107]", path.as_ref()))?
+                .to_vec();
+            sections.push((name, section));
         }
 
         Ok(Some(FileContents {
             layout_version,
-            contents: original_contents,
-            triple_length: triple_len,
-            zip_length: zipped_len,
+            zipped_contents,
+            triple,
+            binary,
+            sections,
         }))
     }
 
     pub fn zipped_contents(&self) -> &[u8] {
-        &self.contents[SHEBANG_TEXT.len() + LENGTH_TYPE_SIZE
-            ..SHEBANG_TEXT.len() + LENGTH_TYPE_SIZE + self.zip_length]
+        &self.zipped_contents
     }
 
     pub fn target_triple(&self) -> Cow<'_, str> {
-        String::from_utf8_lossy(
-            &self.contents[SHEBANG_TEXT.len()
-                + LENGTH_TYPE_SIZE
-                + self.zip_length
-                + LENGTH_TYPE_SIZE
-                ..SHEBANG_TEXT.len()
-                    + LENGTH_TYPE_SIZE
-                    + self.zip_length
-                    + LENGTH_TYPE_SIZE
-                    + self.triple_length],
-        )
+        Cow::Borrowed(&self.triple)
     }
 
     pub fn bin_contents(&self) -> &[u8] {
-        &self.contents[SHEBANG_TEXT.len()
-            + LENGTH_TYPE_SIZE
-            + self.zip_length
-            + LENGTH_TYPE_SIZE
-            + self.triple_length..self.contents.len() - LAYOUT_VERSION_SIZE]
+        &self.binary
     }
 
     pub fn save<P: AsRef<Path>>(&self, path: P, config: &Config) -> Result<(), String> {
-        fs::write(path.as_ref(), &self.contents)
+        self.save_int(path.as_ref(), config, config.backup_count())
+    }
+
+    /// Saves without backing up the file being replaced (e.g. for temporary files)
+    pub fn save_without_backup<P: AsRef<Path>>(
+        &self,
+        path: P,
+        config: &Config,
+    ) -> Result<(), String> {
+        self.save_int(path.as_ref(), config, 0)
+    }
+
+    fn save_int(&self, path: &Path, config: &Config, backup_count: usize) -> Result<(), String> {
+        // Write to a temporary file in the same directory and rename it over the original so
+        // the rss file is never left partially written
+        let dir = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut temp_file = NamedTempFile::new_in(dir)
+            .map_err(|e| format!("E98 Failed to create temporary file: {}", e))?;
+        temp_file
+            .write_all(&self.to_bytes())
+            .and_then(|_| temp_file.as_file().sync_all())
             .map_err(|e| format!("E08 Failed to write file: {}", e))?;
 
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(temp_file.path(), metadata.permissions())
+                .map_err(|e| format!("E99 Failed to copy file permissions: {}", e))?;
+        }
+
         #[cfg(unix)]
         if config.make_rss_executable_linux() {
-            make_executable(path)?;
+            make_executable(temp_file.path())?;
         }
 
+        rotate_backups(path, backup_count)?;
+
+        temp_file
+            .persist(path)
+            .map_err(|e| format!("E100 Failed to replace file: {}", e.error))?;
+
         Ok(())
     }
 }
+
+/// Reads a length followed by that many bytes, advancing the cursor past both
+fn read_length_prefixed<'a>(contents: &'a [u8], cursor: &mut usize) -> Option<&'a [u8]> {
+    if contents.len() < *cursor + LENGTH_TYPE_SIZE {
+        return None;
+    }
+    let length = LengthType::from_le_bytes(
+        contents[*cursor..*cursor + LENGTH_TYPE_SIZE]
+            .try_into()
+            .unwrap(),
+    ) as usize;
+    *cursor += LENGTH_TYPE_SIZE;
+    if contents.len() < *cursor + length {
+        return None;
+    }
+    let data = &contents[*cursor..*cursor + length];
+    *cursor += length;
+    Some(data)
+}
@@ -0,0 +1,283 @@
+use crate::shared::config::Config;
+use crate::shared::util::file_contents::FileContents;
+use crate::shared::util::zip::{ZipEntry, read_zip_entries, zip_entries_to_bytes};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{Cursor, Read, Write, stdin, stdout};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+pub const HISTORY_SECTION: &str = "history";
+
+const REVISIONS_FILE: &str = "revisions.json";
+const BLOB_DIR: &str = "blobs/";
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Revision {
+    id: u64,
+    timestamp: i64,
+    message: Option<String>,
+    files: Vec<RevisionFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+struct RevisionFile {
+    name: String,
+    hash: Option<String>,
+    unix_mode: Option<u32>,
+}
+
+impl Revision {
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    pub fn time(&self) -> String {
+        DateTime::from_timestamp(self.timestamp, 0)
+            .map(|t| {
+                t.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M:%S")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "unknown".to_owned())
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.iter().filter(|f| f.hash.is_some()).count()
+    }
+}
+
+/// Snapshots of a project stored in the history section of an rss file.
+///
+/// Stored as a compressed zip of a revision list and content-addressed file blobs so that files
+/// that don't change between revisions are only stored once.
+#[derive(Default)]
+pub struct History {
+    revisions: Vec<Revision>,
+    blobs: BTreeMap<String, Vec<u8>>,
+}
+
+impl History {
+    pub fn from_file_contents(file_contents: &FileContents) -> Result<History, String> {
+        let Some(bytes) = file_contents.section(HISTORY_SECTION) else {
+            return Ok(History::default());
+        };
+
+        let mut archive = ZipArchive::new(Cursor::new(bytes))
+            .map_err(|e| format!("E109 Failed to open history: {e}"))?;
+
+        let mut history = History::default();
+        for i in 0..archive.len() {
+            let mut file = archive
+                .by_index(i)
+                .map_err(|e| format!("E110 Failed to read history: {e}"))?;
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)
+                .map_err(|e| format!("E110 Failed to read history: {e}"))?;
+
+            if file.name() == REVISIONS_FILE {
+                history.revisions = serde_json::from_slice(&contents)
+                    .map_err(|e| format!("E111 Failed to parse history: {e}"))?;
+            } else if let Some(hash) = file.name().strip_prefix(BLOB_DIR) {
+                history.blobs.insert(hash.to_owned(), contents);
+            }
+        }
+
+        Ok(history)
+    }
+
+    fn to_bytes(&self) -> Result<Vec<u8>, String> {
+        let mut buffer = Cursor::new(Vec::new());
+        let options: FileOptions<()> =
+            FileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip_writer = ZipWriter::new(&mut buffer);
+
+        let revisions = serde_json::to_vec(&self.revisions)
+            .map_err(|e| format!("E112 Failed to serialise history: {e}"))?;
+        zip_writer
+            .start_file(REVISIONS_FILE, options)
+            .and_then(|_| Ok(zip_writer.write_all(&revisions)?))
+            .map_err(|e| format!("E113 Failed to write history: {e}"))?;
+
+        for (hash, contents) in &self.blobs {
+            zip_writer
+                .start_file(format!("{BLOB_DIR}{hash}"), options)
+                .and_then(|_| Ok(zip_writer.write_all(contents)?))
+                .map_err(|e| format!("E113 Failed to write history: {e}"))?;
+        }
+
+        zip_writer
+            .finish()
+            .map_err(|e| format!("E113 Failed to write history: {e}"))?;
+        Ok(buffer.into_inner())
+    }
+
+    /// Writes the history to `file_contents`, removing the section if there are no revisions
+    pub fn save_to(&self, file_contents: &mut FileContents) -> Result<(), String> {
+        if self.revisions.is_empty() {
+            file_contents.set_section(HISTORY_SECTION, None);
+        } else {
+            file_contents.set_section(HISTORY_SECTION, Some(self.to_bytes()?));
+        }
+        Ok(())
+    }
+
+    pub fn revisions(&self) -> &[Revision] {
+        &self.revisions
+    }
+
+    pub fn revision(&self, id: u64) -> Result<&Revision, String> {
+        self.revisions
+            .iter()
+            .find(|r| r.id == id)
+            .ok_or(format!("E114 Revision {id} not found in history"))
+    }
+
+    /// Whether the most recent revision is identical to the given project zip
+    pub fn latest_matches(&self, zipped_contents: &[u8]) -> Result<bool, String> {
+        let Some(latest) = self.revisions.last() else {
+            return Ok(false);
+        };
+        let mut files = read_zip_entries(zipped_contents)?
+            .iter()
+            .map(revision_file)
+            .collect::<Vec<_>>();
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut latest_files = latest.files.iter().collect::<Vec<_>>();
+        latest_files.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(latest_files.into_iter().eq(files.iter()))
+    }
+
+    /// Adds a new revision, keeping at most `max_revisions` revisions
+    pub fn record(
+        &mut self,
+        zipped_contents: &[u8],
+        message: Option<String>,
+        max_revisions: usize,
+    ) -> Result<(), String> {
+        let mut files = Vec::new();
+        for entry in read_zip_entries(zipped_contents)? {
+            let file = revision_file(&entry);
+            if let Some(hash) = &file.hash {
+                self.blobs.entry(hash.clone()).or_insert(entry.contents);
+            }
+            files.push(file);
+        }
+
+        self.revisions.push(Revision {
+            id: self.revisions.last().map_or(1, |r| r.id + 1),
+            timestamp: Local::now().timestamp(),
+            message,
+            files,
+        });
+
+        if self.revisions.len() > max_revisions {
+            let excess = self.revisions.len() - max_revisions;
+            self.revisions.drain(..excess);
+        }
+
+        // Remove blobs no longer referenced by any revision
+        let used = self
+            .revisions
+            .iter()
+            .flat_map(|r| r.files.iter().filter_map(|f| f.hash.as_ref()))
+            .collect::<BTreeSet<_>>();
+        self.blobs.retain(|hash, _| used.contains(hash));
+
+        Ok(())
+    }
+
+    /// Rebuilds the project zip for a revision
+    pub fn revision_zip(&self, id: u64) -> Result<Vec<u8>, String> {
+        let revision = self.revision(id)?;
+        let mut entries = Vec::with_capacity(revision.files.len());
+        for file in &revision.files {
+            let contents = match &file.hash {
+                Some(hash) => self
+                    .blobs
+                    .get(hash)
+                    .ok_or(format!(
+                        "E115 History is missing contents of '{}'",
+                        file.name
+                    ))?
+                    .clone(),
+                None => Vec::new(),
+            };
+            entries.push(ZipEntry {
+                name: file.name.clone(),
+                contents,
+                unix_mode: file.unix_mode,
+                directory: file.hash.is_none(),
+            });
+        }
+        zip_entries_to_bytes(&entries)
+    }
+}
+
+fn revision_file(entry: &ZipEntry) -> RevisionFile {
+    RevisionFile {
+        name: entry.name.clone(),
+        hash: if entry.directory {
+            None
+        } else {
+            Some(format!("{:x}", Sha256::digest(&entry.contents)))
+        },
+        unix_mode: entry.unix_mode,
+    }
+}
+
+/// Records the project in `new_contents` as a new revision if history is enabled in the config,
+/// carrying over the history from `previous_contents`
+pub fn record_history(
+    config: &Config,
+    previous_contents: Option<&FileContents>,
+    new_contents: &mut FileContents,
+    message: Option<String>,
+) -> Result<(), String> {
+    let mut history = match previous_contents {
+        Some(previous) => History::from_file_contents(previous)?,
+        None => History::from_file_contents(new_contents)?,
+    };
+
+    if config.history_length() == 0 {
+        // Keep any existing history untouched
+        return history.save_to(new_contents);
+    }
+
+    if history.latest_matches(new_contents.zipped_contents())? {
+        return history.save_to(new_contents);
+    }
+
+    // Make sure the version being replaced can be returned to
+    if let Some(previous) = previous_contents
+        && !history.latest_matches(previous.zipped_contents())?
+    {
+        history.record(previous.zipped_contents(), None, config.history_length())?;
+    }
+
+    let message = match message {
+        Some(message) => Some(message),
+        None if config.prompt_history_message() => {
+            print!("History message (optional): ");
+            stdout().flush().ok();
+            let mut input = String::new();
+            stdin().read_line(&mut input).ok();
+            let input = input.trim();
+            (!input.is_empty()).then(|| input.to_owned())
+        }
+        None => None,
+    };
+
+    history.record(
+        new_contents.zipped_contents(),
+        message,
+        config.history_length(),
+    )?;
+    history.save_to(new_contents)
+}
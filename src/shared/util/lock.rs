@@ -0,0 +1,160 @@
+use color_print::cprintln;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{Write, stdin, stdout};
+use std::path::{Path, PathBuf};
+
+/// Advisory lock on an rss file, held while it is being edited, recompiled or stripped.
+///
+/// The lock is taken on a hidden `.<file>.lock` file next to the rss file (removed when the lock
+/// is dropped) as the rss file itself is replaced when saving. The state of the rss file when the
+/// lock was taken is recorded so that changes made by anything not respecting the lock can be
+/// detected before saving.
+pub struct RssLock {
+    rss_path: PathBuf,
+    lock_path: PathBuf,
+    _lock_file: File,
+    fingerprint: Option<u64>,
+}
+
+impl RssLock {
+    pub fn acquire<P: AsRef<Path>>(rss_path: P) -> Result<RssLock, String> {
+        let rss_path = rss_path.as_ref().to_path_buf();
+        let file_name = rss_path
+            .file_name()
+            .ok_or(format!("E90 Invalid path: {:?}", rss_path))?
+            .to_string_lossy();
+        let lock_path = rss_path.with_file_name(format!(".{file_name}.lock"));
+
+        loop {
+            let lock_file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(&lock_path)
+                .map_err(|e| {
+                    format!(
+                        "E91 Failed to open lock file '{}': {e}",
+                        lock_path.to_string_lossy()
+                    )
+                })?;
+
+            match lock_file.try_lock() {
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) => {
+                    return Err(format!(
+                        "E92 '{}' is in use by another rss process (locked with '{}')",
+                        rss_path.to_string_lossy(),
+                        lock_path.to_string_lossy()
+                    ));
+                }
+                Err(TryLockError::Error(e)) => {
+                    return Err(format!(
+                        "E93 Failed to lock '{}': {e}",
+                        lock_path.to_string_lossy()
+                    ));
+                }
+            }
+
+            // The previous holder removes the lock file when releasing the lock - if that
+            // happened after we opened it, we are holding a lock nobody else can see
+            if !is_same_file(&lock_file, &lock_path) {
+                continue;
+            }
+
+            let fingerprint = fingerprint(&rss_path)?;
+
+            return Ok(RssLock {
+                rss_path,
+                lock_path,
+                _lock_file: lock_file,
+                fingerprint,
+            });
+        }
+    }
+
+    /// Checks the rss file has not changed since the lock was taken, asking the user what to do
+    /// if it has. Returns the path to save to, or `None` if saving should be aborted.
+    pub fn save_path(&self) -> Result<Option<PathBuf>, String> {
+        if fingerprint(&self.rss_path)? == self.fingerprint {
+            return Ok(Some(self.rss_path.clone()));
+        }
+
+        let side_path = side_file_path(&self.rss_path);
+        cprintln!(
+            "\n<yellow, bold>[!] '{}' was changed by something else since it was read!</>",
+            self.rss_path.to_string_lossy()
+        );
+        cprintln!("  - <cyan,bold>a</>: abort (discard this version)");
+        cprintln!("  - <cyan,bold>o</>: overwrite the changed file");
+        cprintln!(
+            "  - <cyan,bold>s</>: save to '{}' instead",
+            side_path.to_string_lossy()
+        );
+
+        loop {
+            print!("Choose an option (a/o/s): ");
+            stdout().flush().ok();
+            let mut input = String::new();
+            if stdin().read_line(&mut input).unwrap_or(0) == 0 {
+                return Ok(None);
+            }
+            match input.trim().to_ascii_lowercase().as_str() {
+                "a" => return Ok(None),
+                "o" => return Ok(Some(self.rss_path.clone())),
+                "s" => return Ok(Some(side_path)),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Drop for RssLock {
+    fn drop(&mut self) {
+        // The lock itself is released when the file is closed after this
+        let _ = fs::remove_file(&self.lock_path);
+    }
+}
+
+fn fingerprint(path: &Path) -> Result<Option<u64>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let contents = fs::read(path).map_err(|e| format!("E94 Failed to read file: {e}"))?;
+    let mut hasher = DefaultHasher::new();
+    contents.hash(&mut hasher);
+    Ok(Some(hasher.finish()))
+}
+
+fn side_file_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+
+    let mut i = 1;
+    loop {
+        let suffix = if i == 1 { String::new() } else { i.to_string() };
+        let side_path = path.with_file_name(format!("{stem}.conflict{suffix}{extension}"));
+        if !side_path.exists() {
+            return side_path;
+        }
+        i += 1;
+    }
+}
+
+#[cfg(unix)]
+fn is_same_file(file: &File, path: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (file.metadata(), fs::metadata(path)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(windows)]
+fn is_same_file(_file: &File, path: &Path) -> bool {
+    // Files pending deletion can't be opened on Windows so the file existing is sufficient
+    path.exists()
+}
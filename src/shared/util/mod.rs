@@ -1,12 +1,20 @@
 use crate::shared::VERBOSE;
 use crate::shared::config::Config;
 use color_print::cprintln;
+use colored_json::ToColoredJson;
+use serde_json::Value;
+use std::io::{Write, stdout};
 use std::path::{Path, PathBuf};
 
+pub mod backup;
+pub mod binaries;
 pub mod command_fmt;
 pub mod edit_recompile_shared;
+pub mod embed_deps;
 pub mod executable;
 pub mod file_contents;
+pub mod history;
+pub mod lock;
 pub mod zip;
 
 pub fn auto_append_rss<P: AsRef<Path>>(path: P, config: &Config) -> PathBuf {
@@ -25,6 +33,15 @@ pub fn auto_append_rss<P: AsRef<Path>>(path: P, config: &Config) -> PathBuf {
     }
 }
 
+/// Prints JSON output for `--format json` (coloured when printing to a terminal)
+pub fn print_json(value: &Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| format!("E222 Failed to format JSON: {e}"))?
+        .to_colored_json_auto()
+        .map_err(|e| format!("E222 Failed to format JSON: {e}"))?;
+    writeln!(stdout(), "{json}").map_err(|e| format!("E225 Failed to write JSON: {e}"))
+}
+
 #[macro_export]
 macro_rules! time {
     ($description:expr, $important:expr, $($tts:tt)*) => {
@@ -1,8 +1,13 @@
+use crate::shared::JSON_OUTPUT;
+use crate::shared::util::print_json;
+use base64::prelude::{BASE64_STANDARD, Engine};
 use color_print::{cformat, cprintln};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde_json::{Value, json};
 use std::borrow::Cow;
 use std::fs;
 use std::fs::File;
-use std::io::{BufRead, Cursor, Read, Write};
+use std::io::{BufRead, Cursor, Read, Seek, Write};
 use std::path::Path;
 use walkdir::WalkDir;
 use zip::read::ZipFile;
@@ -89,6 +94,71 @@ pub fn unzip_from_bytes<P: AsRef<Path>>(bytes: &[u8], target_dir: P) -> Result<(
     Ok(())
 }
 
+/// A file or directory read from a project zip
+pub struct ZipEntry {
+    pub name: String,
+    pub contents: Vec<u8>,
+    pub unix_mode: Option<u32>,
+    pub directory: bool,
+}
+
+pub fn read_zip_entries(bytes: &[u8]) -> Result<Vec<ZipEntry>, String> {
+    let reader = Cursor::new(bytes);
+    let mut archive =
+        ZipArchive::new(reader).map_err(|e| format!("E28 Failed to open zip: {}", e))?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("E29 Failed to open archive: {}", e))?;
+        let mut contents = Vec::new();
+        if !file.is_dir() {
+            file.read_to_end(&mut contents)
+                .map_err(|e| format!("E108 Failed to read file from zip: {}", e))?;
+        }
+        entries.push(ZipEntry {
+            name: file.mangled_name().to_string_lossy().replace('\\', "/"),
+            contents,
+            unix_mode: file.unix_mode(),
+            directory: file.is_dir(),
+        });
+    }
+
+    Ok(entries)
+}
+
+pub fn zip_entries_to_bytes(entries: &[ZipEntry]) -> Result<Vec<u8>, String> {
+    let mut buffer = Cursor::new(Vec::new());
+    let mut zip_writer = ZipWriter::new(&mut buffer);
+
+    for entry in entries {
+        let mut options: FileOptions<()> =
+            FileOptions::default().compression_method(CompressionMethod::Stored);
+        if let Some(mode) = entry.unix_mode {
+            options = options.unix_permissions(mode);
+        }
+
+        if entry.directory {
+            zip_writer
+                .add_directory(entry.name.trim_end_matches('/').to_string() + "/", options)
+                .map_err(|e| format!("E27 Failed to add directory: {}", e))?;
+        } else {
+            zip_writer
+                .start_file(entry.name.as_str(), options)
+                .map_err(|e| format!("E24 Failed to start zip: {}", e))?;
+            zip_writer
+                .write_all(&entry.contents)
+                .map_err(|e| format!("E25 Failed to write to zip: {}", e))?;
+        }
+    }
+
+    zip_writer
+        .finish()
+        .map_err(|e| format!("E26 Failed to finish zip: {}", e))?;
+    Ok(buffer.into_inner())
+}
+
 #[derive(Debug)]
 struct FileTree {
     name: String,
@@ -198,59 +268,149 @@ pub fn print_tree(bytes: &[u8], file_name: &str, show_hidden: bool) -> Result<()
     Ok(())
 }
 
+/// Lists the files and directories in a project zip for `--format json`
+pub fn files_json(bytes: &[u8], show_hidden: bool) -> Result<Value, String> {
+    let reader = Cursor::new(bytes);
+    let mut archive =
+        ZipArchive::new(reader).map_err(|e| format!("E82 Failed to open zip: {}", e))?;
+
+    let mut files = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .map_err(|e| format!("E29 Failed to open archive: {}", e))?;
+        let hidden = is_hidden(&file);
+        if !show_hidden && hidden {
+            continue;
+        }
+        files.push(json!({
+            "path": file.mangled_name().to_string_lossy().replace('\\', "/"),
+            "directory": file.is_dir(),
+            "size": file.size(),
+            "unix_mode": file.unix_mode(),
+            "hidden": hidden,
+        }));
+    }
+
+    Ok(Value::Array(files))
+}
+
 pub enum Filter {
     None,
     Name(String),
     Extension(String),
+    /// Patterns containing `/` are matched against the full path, others against the file name
+    Globs {
+        paths: GlobSet,
+        names: GlobSet,
+    },
 }
 
-pub fn cat_files(bytes: &[u8], filter: Filter, show_hidden: bool) -> Result<(), String> {
+impl Filter {
+    pub fn from_globs(patterns: &[String]) -> Result<Filter, String> {
+        let mut paths = GlobSetBuilder::new();
+        let mut names = GlobSetBuilder::new();
+        for pattern in patterns {
+            let glob = GlobBuilder::new(pattern)
+                .literal_separator(true)
+                .build()
+                .map_err(|e| format!("E133 Invalid pattern '{pattern}': {e}"))?;
+            if pattern.contains('/') {
+                paths.add(glob);
+            } else {
+                names.add(glob);
+            }
+        }
+        Ok(Filter::Globs {
+            paths: paths
+                .build()
+                .map_err(|e| format!("E134 Invalid patterns: {e}"))?,
+            names: names
+                .build()
+                .map_err(|e| format!("E134 Invalid patterns: {e}"))?,
+        })
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        match self {
+            Filter::None => true,
+            Filter::Name(name) => path
+                .file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| &n == name),
+            Filter::Extension(extension) => path
+                .extension()
+                .map(|e| e.to_string_lossy())
+                .is_some_and(|e| &e == extension),
+            Filter::Globs { paths, names } => {
+                let unix_path = path
+                    .iter()
+                    .map(|p| p.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                paths.is_match(&unix_path) || path.file_name().is_some_and(|n| names.is_match(n))
+            }
+        }
+    }
+}
+
+pub struct CatOptions {
+    pub show_hidden: bool,
+    /// Write file contents to stdout exactly, without headers or decoration
+    pub raw: bool,
+    pub line_numbers: bool,
+}
+
+pub fn cat_files(bytes: &[u8], filter: Filter, options: CatOptions) -> Result<(), String> {
     let reader = Cursor::new(bytes);
     let mut archive =
         ZipArchive::new(reader).map_err(|e| format!("E28 Failed to open zip: {}", e))?;
 
+    if *JSON_OUTPUT.get().unwrap() {
+        return cat_files_json(&mut archive, filter, options);
+    }
+
     let mut shown = false;
     for i in 0..archive.len() {
-        let file = archive
+        let mut file = archive
             .by_index(i)
             .map_err(|e| format!("E29 Failed to open archive: {}", e))?;
-        if file.is_dir() || (!show_hidden && is_hidden(&file)) {
+        if file.is_dir() || (!options.show_hidden && is_hidden(&file)) {
             continue;
         }
         let path = file.mangled_name();
 
-        match &filter {
-            Filter::None => {}
-            Filter::Name(name) => {
-                if path
-                    .file_name()
-                    .map(|n| n.to_string_lossy())
-                    .is_none_or(|n| &n != name)
-                {
-                    continue;
-                }
-            }
-            Filter::Extension(extension) => {
-                if path
-                    .extension()
-                    .map(|e| e.to_string_lossy())
-                    .is_none_or(|e| &e != extension)
-                {
-                    continue;
-                }
-            }
+        if !filter.matches(&path) {
+            continue;
         }
 
         shown = true;
 
+        if options.raw {
+            let mut stdout = std::io::stdout().lock();
+            std::io::copy(&mut file, &mut stdout)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("E135 Failed to write file contents: {}", e))?;
+            continue;
+        }
+
         println!("{}", path.to_string_lossy());
 
-        let mut lines = std::io::BufReader::new(file).lines();
-        let mut first = true;
-        while let Some(Ok(line)) = lines.next() {
-            if first {
+        let lines = std::io::BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .collect::<Vec<_>>();
+        let number_width = lines.len().to_string().len();
+        for (i, line) in lines.iter().enumerate() {
+            if options.line_numbers {
+                cprintln!(
+                    "<cyan>{:>width$} │ </>{}",
+                    i + 1,
+                    line,
+                    width = number_width
+                );
+            } else if i == 0 {
                 cprintln!("<cyan>└─┐ </>{}", line);
-                first = false;
             } else {
                 cprintln!("<cyan>  │ </>{}", line);
             }
@@ -260,8 +420,53 @@ pub fn cat_files(bytes: &[u8], filter: Filter, show_hidden: bool) -> Result<(),
     }
 
     if !shown {
-        cprintln!("<yellow, bold>No files found</>")
+        if options.raw {
+            eprintln!("No files found");
+        } else {
+            cprintln!("<yellow, bold>No files found</>")
+        }
     }
 
     Ok(())
 }
+
+fn cat_files_json<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    filter: Filter,
+    options: CatOptions,
+) -> Result<(), String> {
+    if options.raw {
+        return Err("E223 `--raw` can't be used with `--format json`".to_owned());
+    }
+
+    let mut files = Vec::new();
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("E29 Failed to open archive: {}", e))?;
+        if file.is_dir() || (!options.show_hidden && is_hidden(&file)) {
+            continue;
+        }
+        let path = file.mangled_name();
+        if !filter.matches(&path) {
+            continue;
+        }
+
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)
+            .map_err(|e| format!("E108 Failed to read file from zip: {}", e))?;
+        let (encoding, contents) = match String::from_utf8(contents) {
+            Ok(text) => ("utf8", text),
+            Err(e) => ("base64", BASE64_STANDARD.encode(e.into_bytes())),
+        };
+        files.push(json!({
+            "path": path.to_string_lossy().replace('\\', "/"),
+            "size": file.size(),
+            "unix_mode": file.unix_mode(),
+            "encoding": encoding,
+            "contents": contents,
+        }));
+    }
+
+    print_json(&Value::Array(files))
+}
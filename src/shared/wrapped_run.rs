@@ -2,12 +2,17 @@ use crate::shared::VERBOSE;
 use crate::shared::config::get_config;
 use crate::shared::recompile::recompile;
 use crate::shared::run::{RunParam, run};
+use crate::shared::util::binaries::{BinaryTarget, missing_target_error};
 use color_print::cprintln;
 use std::process::exit;
 
-pub fn wrapped_run(file: &str, args: &[String]) -> Result<(), String> {
+pub fn wrapped_run(
+    file: &str,
+    target: Option<&BinaryTarget>,
+    args: &[String],
+) -> Result<(), String> {
     let config = get_config()?;
-    let binary_exists = run(&config, RunParam::Path(&file), args)?;
+    let binary_exists = run(&config, RunParam::Path(&file), target, args)?;
 
     // Build and re-run if binary doesn't exist
     let code = match binary_exists {
@@ -15,11 +20,23 @@ pub fn wrapped_run(file: &str, args: &[String]) -> Result<(), String> {
         Err(no_binary_reason) => {
             cprintln!("<yellow, bold>[!] {no_binary_reason} - recompiling...</>");
             let compiled_binary = recompile(&config, file)?;
-            if let Some(compiled_binary) = compiled_binary {
+            if let Some(compiled_binaries) = compiled_binary {
+                let Some(binary) = compiled_binaries.get(target) else {
+                    return Err(missing_target_error(
+                        target.unwrap(),
+                        compiled_binaries.targets(),
+                    ));
+                };
                 if !VERBOSE.get().unwrap() {
                     println!("Running binary...");
                 }
-                run(&config, RunParam::<String>::Binary(compiled_binary), args)?.unwrap_or(-1)
+                run(
+                    &config,
+                    RunParam::<String>::Binary(binary.to_vec()),
+                    None,
+                    args,
+                )?
+                .unwrap_or(-1)
             } else {
                 -1
             }